@@ -0,0 +1,259 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Shortest hex prefix accepted in place of a full event ID.
+pub const MIN_PREFIX_LEN: usize = 4;
+
+/// Longest alias derived from an event name.
+const MAX_ALIAS_LEN: usize = 32;
+
+/// An event this machine has created or attended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownEvent {
+	pub network: String,
+	pub event_id: String,
+	/// Short name that stands for the event on the command line.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub alias: Option<String>,
+}
+
+/// Events known locally, so commands can take an alias or a short prefix
+/// of the event ID, like git short hashes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EventAliases {
+	#[serde(default)]
+	pub events: Vec<KnownEvent>,
+}
+
+impl EventAliases {
+	/// Path to the alias table.
+	pub fn path() -> PathBuf {
+		Config::dir().join("aliases.json")
+	}
+
+	/// Load the table, which is empty if the file does not exist yet.
+	pub fn load() -> Result<Self> {
+		let path = Self::path();
+		if !path.exists() {
+			return Ok(Self::default());
+		}
+		let content = std::fs::read_to_string(&path)?;
+		serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid alias table", path.display()))
+	}
+
+	pub fn save(&self) -> Result<()> {
+		let path = Self::path();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+
+	/// Turn `query` into an event ID on `network`.  Accepts, in order: an
+	/// alias, a full 64-character ID, or a unique prefix of at least
+	/// [`MIN_PREFIX_LEN`] characters of a known event's ID.
+	pub fn resolve(&self, network: &str, query: &str) -> Result<String> {
+		let query = query.trim();
+		let known = || self.events.iter().filter(|e| e.network == network);
+		if let Some(event) = known().find(|e| e.alias.as_deref() == Some(query)) {
+			return Ok(event.event_id.clone());
+		}
+
+		let hex = query.strip_prefix("0x").unwrap_or(query).to_ascii_lowercase();
+		if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+			anyhow::bail!(
+				"unknown event {query:?}: not an event ID, and no event on {network} has that alias"
+			);
+		}
+		if hex.len() == 64 {
+			return Ok(hex);
+		}
+		if hex.len() < MIN_PREFIX_LEN {
+			anyhow::bail!(
+				"event ID prefix {query:?} is too short; give at least {MIN_PREFIX_LEN} characters"
+			);
+		}
+
+		let matches: Vec<&KnownEvent> = known().filter(|e| e.event_id.starts_with(&hex)).collect();
+		match matches.as_slice() {
+			[event] => Ok(event.event_id.clone()),
+			[] => anyhow::bail!(
+				"no known event on {network} starts with {query:?}; give the full event ID"
+			),
+			_ => anyhow::bail!(
+				"event ID prefix {query:?} is ambiguous on {network}:\n  {}",
+				matches
+					.iter()
+					.map(|e| describe(e))
+					.collect::<Vec<_>>()
+					.join("\n  ")
+			),
+		}
+	}
+
+	/// Remember `event_id` on `network`, aliased from `name` if given and the
+	/// event has no alias yet.  Returns the event's alias.
+	pub fn remember(&mut self, network: &str, event_id: &str, name: Option<&str>) -> Option<String> {
+		let index = match self
+			.events
+			.iter()
+			.position(|e| e.network == network && e.event_id == event_id)
+		{
+			Some(i) => i,
+			None => {
+				self.events.push(KnownEvent {
+					network: network.to_owned(),
+					event_id: event_id.to_owned(),
+					alias: None,
+				});
+				self.events.len() - 1
+			}
+		};
+		if self.events[index].alias.is_none() {
+			if let Some(base) = name.and_then(slug) {
+				let alias = (1..)
+					.map(|n| if n == 1 { base.clone() } else { format!("{base}-{n}") })
+					.find(|a| !self.alias_taken(network, a))
+					.expect("an unused suffix exists");
+				self.events[index].alias = Some(alias);
+			}
+		}
+		self.events[index].alias.clone()
+	}
+
+	/// Give `event_id` the alias `alias` on `network`, replacing any it had.
+	pub fn set_alias(&mut self, network: &str, event_id: &str, alias: &str) -> Result<()> {
+		check_alias(alias)?;
+		if let Some(other) = self.events.iter().find(|e| {
+			e.network == network && e.alias.as_deref() == Some(alias) && e.event_id != event_id
+		}) {
+			anyhow::bail!("alias {alias:?} already stands for event {}", other.event_id);
+		}
+		self.remember(network, event_id, None);
+		if let Some(event) = self
+			.events
+			.iter_mut()
+			.find(|e| e.network == network && e.event_id == event_id)
+		{
+			event.alias = Some(alias.to_owned());
+		}
+		Ok(())
+	}
+
+	fn alias_taken(&self, network: &str, alias: &str) -> bool {
+		self.events
+			.iter()
+			.any(|e| e.network == network && e.alias.as_deref() == Some(alias))
+	}
+}
+
+/// `alias (event_id)`, or just the event ID.
+pub fn describe(event: &KnownEvent) -> String {
+	match &event.alias {
+		Some(alias) => format!("{alias} ({})", event.event_id),
+		None => event.event_id.clone(),
+	}
+}
+
+/// Aliases are lowercase letters, digits, `-` and `_`, start with a letter,
+/// and are at most [`MAX_ALIAS_LEN`] characters.  An alias of only hex
+/// digits would read as an ID prefix, so it is refused.
+fn check_alias(alias: &str) -> Result<()> {
+	let valid = alias.len() <= MAX_ALIAS_LEN
+		&& alias.starts_with(|c: char| c.is_ascii_lowercase())
+		&& alias
+			.chars()
+			.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+		&& !alias.chars().all(|c| c.is_ascii_hexdigit());
+	if !valid {
+		anyhow::bail!(
+			"invalid alias {alias:?}: use up to {MAX_ALIAS_LEN} lowercase letters, digits, \
+			 '-' or '_', starting with a letter, and not only hex digits"
+		);
+	}
+	Ok(())
+}
+
+/// Derive an alias from an event name: `"CKB Meetup #3"` becomes
+/// `ckb-meetup-3`.  `None` if nothing usable is left.
+fn slug(name: &str) -> Option<String> {
+	let mut slug = String::new();
+	for c in name.chars().flat_map(char::to_lowercase) {
+		if c.is_ascii_alphanumeric() {
+			slug.push(c);
+		} else if !slug.is_empty() && !slug.ends_with('-') {
+			slug.push('-');
+		}
+	}
+	slug.truncate(MAX_ALIAS_LEN);
+	let slug = slug.trim_matches('-');
+	let slug = slug.trim_start_matches(|c: char| c.is_ascii_digit() || c == '-');
+	check_alias(slug).ok()?;
+	Some(slug.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn id(prefix: &str) -> String {
+		format!("{prefix}{}", "0".repeat(64 - prefix.len()))
+	}
+
+	#[test]
+	fn names_become_unique_aliases() {
+		assert_eq!(slug("CKB Meetup #3").as_deref(), Some("ckb-meetup-3"));
+		assert_eq!(slug("  2026: Nervos Summit!  ").as_deref(), Some("nervos-summit"));
+		assert_eq!(slug("###"), None);
+		assert_eq!(slug("Cafe"), None);
+
+		let mut table = EventAliases::default();
+		assert_eq!(table.remember("testnet", &id("aa"), Some("Meetup")).as_deref(), Some("meetup"));
+		assert_eq!(table.remember("testnet", &id("bb"), Some("Meetup")).as_deref(), Some("meetup-2"));
+		assert_eq!(table.remember("mainnet", &id("cc"), Some("Meetup")).as_deref(), Some("meetup"));
+		// Known events keep their alias.
+		assert_eq!(table.remember("testnet", &id("aa"), Some("Other")).as_deref(), Some("meetup"));
+		assert_eq!(table.remember("testnet", &id("dd"), None), None);
+		assert_eq!(table.events.len(), 4);
+	}
+
+	#[test]
+	fn resolves_aliases_ids_and_unique_prefixes() {
+		let mut table = EventAliases::default();
+		table.remember("testnet", &id("abcd1"), Some("Meetup"));
+		table.remember("testnet", &id("abcd2"), None);
+		table.remember("mainnet", &id("ffff"), None);
+
+		assert_eq!(table.resolve("testnet", "meetup").unwrap(), id("abcd1"));
+		assert_eq!(table.resolve("testnet", "abcd1").unwrap(), id("abcd1"));
+		assert_eq!(table.resolve("testnet", "0xABCD2").unwrap(), id("abcd2"));
+		// Full IDs pass through even when unknown.
+		assert_eq!(table.resolve("testnet", &id("12")).unwrap(), id("12"));
+
+		let ambiguous = table.resolve("testnet", "abcd").unwrap_err().to_string();
+		assert!(ambiguous.contains("meetup (abcd1"), "{ambiguous}");
+		assert!(table.resolve("testnet", "abc").is_err());
+		assert!(table.resolve("testnet", "ffff").is_err());
+		assert!(table.resolve("testnet", "nope").is_err());
+	}
+
+	#[test]
+	fn explicit_aliases_are_checked() {
+		let mut table = EventAliases::default();
+		table.set_alias("testnet", &id("aa"), "devcon").unwrap();
+		assert_eq!(table.resolve("testnet", "devcon").unwrap(), id("aa"));
+		table.set_alias("testnet", &id("aa"), "devcon-2026").unwrap();
+		assert!(table.resolve("testnet", "devcon").is_err());
+
+		assert!(table.set_alias("testnet", &id("bb"), "devcon-2026").is_err());
+		assert!(table.set_alias("testnet", &id("bb"), "beef").is_err());
+		assert!(table.set_alias("testnet", &id("bb"), "Dev Con").is_err());
+		assert!(table.set_alias("testnet", &id("bb"), "event.toml").is_err());
+	}
+}
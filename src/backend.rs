@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::config::BackendConfig;
+use crate::delegation::Delegation;
+use crate::event::EventMetadata;
+
+/// Upper bound on a single backend round trip.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Typed client for the ckb-pop.xyz event registry API.
+///
+/// The registry assigns canonical event IDs and records which anchor
+/// transaction proves each event on-chain.  Its base URL comes from
+/// [`BackendConfig`], so a self-hosted registry or a local mock can stand
+/// in for the public one.
+pub struct BackendClient {
+	api_url: String,
+	frontend_url: String,
+	http: reqwest::Client,
+}
+
+/// Errors surfaced by [`BackendClient`].
+#[derive(Debug, thiserror::Error)]
+pub enum BackendError {
+	/// The request never produced a response (connect, timeout, TLS...).
+	#[error("{path}: could not reach backend: {source}")]
+	Transport {
+		path: String,
+		#[source]
+		source: reqwest::Error,
+	},
+	/// The backend answered with a non-success status.
+	#[error("{path}: backend returned {status}: {message}")]
+	Api {
+		path: String,
+		status: u16,
+		message: String,
+	},
+	/// The response could not be decoded into the expected type.
+	#[error("{path}: unexpected response: {reason}")]
+	Decode { path: String, reason: String },
+}
+
+/// Body of `POST /events/create`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateEventRequest {
+	pub creator_address: String,
+	/// Signature over `CKB-PoP-CreateEvent|<nonce>`.
+	pub creator_signature: String,
+	pub nonce: String,
+	pub metadata: EventMetadata,
+}
+
+/// Reply to `POST /events/create`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateEventResponse {
+	/// Canonical event ID to anchor on-chain.
+	pub event_id: String,
+}
+
+/// Body of `POST /events/{id}/activate`.
+#[derive(Debug, Clone, Serialize)]
+struct ActivateEventRequest<'a> {
+	tx_hash: &'a str,
+}
+
+/// Error body the backend sends with non-success statuses.
+#[derive(Deserialize)]
+struct ErrorBody {
+	error: Option<String>,
+}
+
+impl BackendClient {
+	pub fn new(config: &BackendConfig) -> Self {
+		let http = reqwest::Client::builder()
+			.timeout(REQUEST_TIMEOUT)
+			.build()
+			.expect("failed to build HTTP client");
+		Self {
+			api_url: config.api_url.trim_end_matches('/').to_owned(),
+			frontend_url: config.frontend_url.trim_end_matches('/').to_owned(),
+			http,
+		}
+	}
+
+	/// Public page for an event.
+	pub fn event_url(&self, event_id: &str) -> String {
+		format!("{}/events/{event_id}", self.frontend_url)
+	}
+
+	/// Full URL of an API path, for printing manual fallbacks.
+	pub fn api_endpoint(&self, path: &str) -> String {
+		format!("{}{path}", self.api_url)
+	}
+
+	/// Register an event and receive its canonical ID.
+	pub async fn create_event(&self, req: &CreateEventRequest) -> Result<CreateEventResponse> {
+		self.post("/events/create", req).await
+	}
+
+	/// Record the anchor transaction for an event.  Idempotent.
+	pub async fn activate_event(&self, event_id: &str, tx_hash: &str) -> Result<()> {
+		let path = format!("/events/{event_id}/activate");
+		let request = self
+			.http
+			.post(self.api_endpoint(&path))
+			.json(&ActivateEventRequest { tx_hash });
+		self.send(&path, request).await?;
+		Ok(())
+	}
+
+	/// The metadata the registry holds for an event.
+	pub async fn event_metadata(&self, event_id: &str) -> Result<EventMetadata> {
+		let (path, record) = self.event_record(event_id).await?;
+		EventMetadata::from_json(record).map_err(|e| {
+			BackendError::Decode {
+				path,
+				reason: e.to_string(),
+			}
+			.into()
+		})
+	}
+
+	/// The creator address the registry recorded for an event when its
+	/// creator signed the creation request.
+	pub async fn event_creator(&self, event_id: &str) -> Result<String> {
+		let (path, record) = self.event_record(event_id).await?;
+		match record["creator_address"].as_str() {
+			Some(creator) => Ok(creator.to_owned()),
+			None => Err(BackendError::Decode {
+				path,
+				reason: "the event record names no creator_address".into(),
+			}
+			.into()),
+		}
+	}
+
+	async fn event_record(&self, event_id: &str) -> Result<(String, serde_json::Value)> {
+		let path = format!("/events/{event_id}");
+		let request = self.http.get(self.api_endpoint(&path));
+		let record = self.decode(&path, request).await?;
+		Ok((path, record))
+	}
+
+	/// Publish a creator's delegation so delegates and verifiers can fetch it.
+	pub async fn publish_delegation(&self, delegation: &Delegation) -> Result<()> {
+		let path = format!("/events/{}/delegations", delegation.event_id);
+		let request = self.http.post(self.api_endpoint(&path)).json(delegation);
+		self.send(&path, request).await?;
+		Ok(())
+	}
+
+	/// The delegations published for an event.
+	pub async fn delegations(&self, event_id: &str) -> Result<Vec<Delegation>> {
+		let path = format!("/events/{event_id}/delegations");
+		let request = self.http.get(self.api_endpoint(&path));
+		self.decode(&path, request).await
+	}
+
+	/// POST `body` and decode the JSON reply.
+	async fn post<B, R>(&self, path: &str, body: &B) -> Result<R>
+	where
+		B: Serialize + ?Sized,
+		R: DeserializeOwned,
+	{
+		self.decode(path, self.http.post(self.api_endpoint(path)).json(body)).await
+	}
+
+	/// Send `request` and decode the JSON reply.
+	async fn decode<R: DeserializeOwned>(
+		&self,
+		path: &str,
+		request: reqwest::RequestBuilder,
+	) -> Result<R> {
+		let bytes = self.send(path, request).await?;
+		serde_json::from_slice(&bytes).map_err(|e| {
+			BackendError::Decode {
+				path: path.to_owned(),
+				reason: e.to_string(),
+			}
+			.into()
+		})
+	}
+
+	/// Send `request` and return the raw reply, turning non-success
+	/// statuses into [`BackendError::Api`] with the backend's `error` message.
+	async fn send(&self, path: &str, request: reqwest::RequestBuilder) -> Result<Vec<u8>> {
+		let resp = request
+			.send()
+			.await
+			.map_err(|source| BackendError::Transport {
+				path: path.to_owned(),
+				source,
+			})?;
+
+		let status = resp.status();
+		let bytes = resp.bytes().await.map_err(|source| BackendError::Transport {
+			path: path.to_owned(),
+			source,
+		})?;
+		if !status.is_success() {
+			let message = serde_json::from_slice::<ErrorBody>(&bytes)
+				.ok()
+				.and_then(|b| b.error)
+				.unwrap_or_else(|| {
+					status.canonical_reason().unwrap_or("unknown error").to_owned()
+				});
+			return Err(BackendError::Api {
+				path: path.to_owned(),
+				status: status.as_u16(),
+				message,
+			}
+			.into());
+		}
+		Ok(bytes.to_vec())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock;
+
+	async fn mock_backend(status: &'static str, body: &'static str) -> BackendConfig {
+		BackendConfig {
+			api_url: format!("{}/api", mock::respond_with(status, body).await),
+			frontend_url: "https://example.test/".into(),
+		}
+	}
+
+	fn request() -> CreateEventRequest {
+		CreateEventRequest {
+			creator_address: "ckt1qcreator".into(),
+			creator_signature: "0xsig".into(),
+			nonce: "n".into(),
+			metadata: EventMetadata {
+				name: "Meetup".into(),
+				description: "".into(),
+				image_url: None,
+				location: None,
+				start_time: None,
+				end_time: None,
+			},
+		}
+	}
+
+	#[tokio::test]
+	async fn decodes_created_event() {
+		let config = mock_backend("200 OK", r#"{"event_id":"evt_1","status":"pending"}"#).await;
+		let backend = BackendClient::new(&config);
+		let created = backend.create_event(&request()).await.unwrap();
+		assert_eq!(created.event_id, "evt_1");
+		assert_eq!(backend.event_url("evt_1"), "https://example.test/events/evt_1");
+	}
+
+	#[tokio::test]
+	async fn surfaces_backend_errors() {
+		let config = mock_backend("400 Bad Request", r#"{"error":"bad signature"}"#).await;
+		let err = BackendClient::new(&config).create_event(&request()).await.unwrap_err();
+		match err.downcast_ref::<BackendError>() {
+			Some(BackendError::Api { status, message, .. }) => {
+				assert_eq!(*status, 400);
+				assert_eq!(message, "bad signature");
+			}
+			other => panic!("expected an API error, got {other:?}"),
+		}
+
+		let config = mock_backend("502 Bad Gateway", "<html>").await;
+		let err = BackendClient::new(&config).activate_event("evt", "0x01").await.unwrap_err();
+		assert!(err.to_string().contains("502: Bad Gateway"), "{err}");
+	}
+
+	#[tokio::test]
+	async fn reads_event_metadata_from_the_record() {
+		let config = mock_backend(
+			"200 OK",
+			r#"{"event_id":"evt","creator_address":"ckt1qcreator","metadata":{"name":"Meetup","description":"d","location":"Berlin"}}"#,
+		)
+		.await;
+		let backend = BackendClient::new(&config);
+		let metadata = backend.event_metadata("evt").await.unwrap();
+		assert_eq!(metadata.name, "Meetup");
+		assert_eq!(metadata.location.as_deref(), Some("Berlin"));
+		assert_eq!(metadata.start_time, None);
+		assert_eq!(backend.event_creator("evt").await.unwrap(), "ckt1qcreator");
+	}
+
+	#[tokio::test]
+	async fn decodes_published_delegations() {
+		let config = mock_backend(
+			"200 OK",
+			r#"[{"version":1,"network":"testnet","event_id":"evt","creator_address":"ckt1qcreator","delegate_address":"ckt1qdelegate","signature":"0x01"}]"#,
+		)
+		.await;
+		let delegations = BackendClient::new(&config).delegations("evt").await.unwrap();
+		assert_eq!(delegations.len(), 1);
+		assert_eq!(delegations[0].delegate_address, "ckt1qdelegate");
+		assert_eq!(delegations[0].expires_at, None);
+	}
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
@@ -7,7 +9,8 @@ use clap::{Parser, Subcommand, ValueEnum};
 	version
 )]
 pub struct Cli {
-	/// Network to connect to.
+	/// Network to connect to: testnet, mainnet, devnet, or a network
+	/// defined in the contract registry.
 	#[arg(long, default_value = "testnet", global = true)]
 	pub network: Network,
 
@@ -23,21 +26,46 @@ pub struct Cli {
 	#[arg(long, global = true)]
 	pub address: Option<String>,
 
+	/// Check the contract deployments on-chain before signing anything.
+	#[arg(long, global = true)]
+	pub verify_contracts: bool,
+
 	#[command(subcommand)]
 	pub command: Command,
 }
 
-#[derive(Clone, ValueEnum)]
-pub enum Network {
-	Testnet,
-	Mainnet,
-}
+/// A network name: `testnet`, `mainnet`, `devnet`, or any network with
+/// contract deployments in config.  Names other than `mainnet` and
+/// `devnet` use the testnet RPC endpoints and address format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Network(String);
 
 impl Network {
 	pub fn as_str(&self) -> &str {
-		match self {
-			Self::Testnet => "testnet",
-			Self::Mainnet => "mainnet",
+		&self.0
+	}
+
+	/// Address format of the network's chain.
+	pub fn address_type(&self) -> ckb_sdk::NetworkType {
+		match self.as_str() {
+			"mainnet" => ckb_sdk::NetworkType::Mainnet,
+			"devnet" => ckb_sdk::NetworkType::Dev,
+			_ => ckb_sdk::NetworkType::Testnet,
+		}
+	}
+}
+
+impl std::str::FromStr for Network {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let valid = !s.is_empty()
+			&& s.bytes()
+				.all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+		if valid {
+			Ok(Self(s.to_owned()))
+		} else {
+			Err("network names use lowercase letters, digits, '-' and '_'".into())
 		}
 	}
 }
@@ -66,8 +94,22 @@ pub enum Command {
 
 	/// Scan QR, verify attendance, and mint a badge in one step.
 	Attend {
-		/// QR code data in the format event_id|timestamp|hmac.
-		qr_data: String,
+		/// QR code data: an attend link (https://ckb-pop.xyz/a?e=...) or
+		/// v2|event_id|timestamp|hmac|... (event_id|timestamp|hmac also works).
+		#[arg(required_unless_present = "flush")]
+		qr_data: Option<String>,
+
+		/// Wait for the badge tx to commit, optionally N more blocks deep.
+		#[arg(long, value_name = "CONFIRMATIONS", num_args = 0..=1, default_missing_value = "0")]
+		wait: Option<u64>,
+
+		/// Check the transaction against the node and stop before signing.
+		#[arg(long)]
+		dry_run: bool,
+
+		/// Mint the badges of attendances queued while offline.
+		#[arg(long, conflicts_with_all = ["qr_data", "dry_run"])]
+		flush: bool,
 	},
 
 	/// Mint and query soulbound badges.
@@ -76,11 +118,23 @@ pub enum Command {
 		command: BadgeCommand,
 	},
 
-	/// Check transaction status on-chain.
+	/// Verify saved attendance proofs.
+	Proof {
+		#[command(subcommand)]
+		command: ProofCommand,
+	},
+
+	/// Inspect and track transactions on-chain.
 	Tx {
 		#[command(subcommand)]
 		command: TxCommand,
 	},
+
+	/// Check the PoP contract deployments.
+	Contracts {
+		#[command(subcommand)]
+		command: ContractsCommand,
+	},
 }
 
 // -- Signer subcommands --
@@ -107,13 +161,22 @@ pub enum SignerCommand {
 pub enum EventCommand {
 	/// Create a new event and anchor it on-chain.
 	Create {
+		/// Read the event from a TOML or JSON manifest instead of flags.
+		/// The event ID is stored next to it afterwards.
+		#[arg(
+			long,
+			value_name = "PATH",
+			conflicts_with_all = ["name", "description", "image_url", "location", "start", "end"]
+		)]
+		from: Option<PathBuf>,
+
 		/// Event name.
-		#[arg(long)]
-		name: String,
+		#[arg(long, required_unless_present = "from")]
+		name: Option<String>,
 
 		/// Event description.
-		#[arg(long)]
-		description: String,
+		#[arg(long, required_unless_present = "from")]
+		description: Option<String>,
 
 		/// URL for the event image.
 		#[arg(long)]
@@ -123,13 +186,29 @@ pub enum EventCommand {
 		#[arg(long)]
 		location: Option<String>,
 
-		/// Start time (ISO 8601).
+		/// Start time with a timezone, e.g. 2026-06-01T10:00:00Z or
+		/// "2026-06-01 10:00 +02:00".  Stored in UTC.
 		#[arg(long)]
 		start: Option<String>,
 
-		/// End time (ISO 8601).
+		/// End time with a timezone; must be after the start.
 		#[arg(long)]
 		end: Option<String>,
+
+		/// Wait for the anchor tx to commit, optionally N more blocks deep,
+		/// before activating.  Without it, activation waits up to 90 s.
+		#[arg(long, value_name = "CONFIRMATIONS", num_args = 0..=1, default_missing_value = "0")]
+		wait: Option<u64>,
+
+		/// Print the metadata hash and the anchor transaction, check it
+		/// against the node, and stop before signing.
+		#[arg(long)]
+		dry_run: bool,
+
+		/// Skip the ckb-pop.xyz registry: derive the event ID locally,
+		/// anchor a hash of the full metadata, and print an event descriptor.
+		#[arg(long)]
+		no_backend: bool,
 	},
 
 	/// List events visible on-chain.
@@ -139,20 +218,79 @@ pub enum EventCommand {
 		creator: Option<String>,
 	},
 
-	/// Show details of an on-chain event anchor.
+	/// Show an on-chain event anchor and check its metadata against it.
 	Show {
-		/// Event ID (64-character hex string).
+		/// Event ID, alias, unique ID prefix, or event manifest path.
 		event_id: String,
+
+		/// Metadata to check instead of the registry's: a JSON file or URL
+		/// holding the metadata, an event descriptor, or a backend record.
+		#[arg(long, value_name = "PATH|URL")]
+		metadata: Option<String>,
 	},
 
 	/// Open an attendance window and display rotating QR codes.
 	Window {
-		/// Event ID (64-character hex string).
+		/// Event ID, alias, unique ID prefix, or event manifest path.
+		event_id: String,
+
+		/// Window duration in minutes. Use 0 for open-ended.  Defaults to
+		/// the rest of the manifest window open now, otherwise 60.
+		#[arg(long, conflicts_with = "schedule")]
+		duration: Option<u64>,
+
+		/// Run a schedule of windows signed up front: show QR codes while a
+		/// window is open and a countdown otherwise.  Reads the windows from
+		/// PATH, or from the event manifest if no PATH is given.
+		#[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "")]
+		schedule: Option<String>,
+
+		/// Open the window as a co-organizer with this delegation from the
+		/// event creator.  Looked up locally or in the registry if omitted.
+		#[arg(long, value_name = "PATH")]
+		delegation: Option<PathBuf>,
+
+		/// What the QR codes hold: the bare pipe-delimited payload, or an
+		/// attend link that phone cameras open.
+		#[arg(long, default_value = "pipe")]
+		qr_format: QrFormat,
+	},
+
+	/// Authorize other addresses to open attendance windows for an event
+	/// (creator action).
+	Delegate {
+		/// Event ID, alias, unique ID prefix, or event manifest path.
 		event_id: String,
 
-		/// Window duration in minutes. Use 0 for open-ended.
-		#[arg(long, default_value = "60")]
-		duration: u64,
+		/// Co-organizer address; repeat for several.  Defaults to the
+		/// manifest's co_organizers.
+		#[arg(long = "to", value_name = "ADDRESS")]
+		to: Vec<String>,
+
+		/// When the delegation ends, as a date and time with a timezone.
+		#[arg(long)]
+		expires: Option<String>,
+	},
+
+	/// List known events and their aliases, or give an event an alias.
+	Alias {
+		/// New alias: lowercase letters, digits, '-' and '_'.
+		#[arg(requires = "event")]
+		alias: Option<String>,
+
+		/// Event to alias: ID, unique ID prefix, or event manifest path.
+		event: Option<String>,
+	},
+
+	/// Record an event's anchor transaction with the backend once it has
+	/// committed.  Resumes an `event create` that stopped waiting.
+	Activate {
+		/// Event ID, alias, unique ID prefix, or event manifest path.
+		event_id: String,
+
+		/// Anchor transaction hash.  Looked up locally or on-chain if omitted.
+		#[arg(long, value_name = "TX_HASH")]
+		tx: Option<String>,
 	},
 }
 
@@ -162,12 +300,20 @@ pub enum EventCommand {
 pub enum BadgeCommand {
 	/// Mint a soulbound badge for an attendee (organizer action).
 	Mint {
-		/// Event ID (64-character hex string).
+		/// Event ID, alias, unique ID prefix, or event manifest path.
 		event_id: String,
 
 		/// Recipient CKB address.
 		#[arg(long)]
 		to: String,
+
+		/// Wait for the badge tx to commit, optionally N more blocks deep.
+		#[arg(long, value_name = "CONFIRMATIONS", num_args = 0..=1, default_missing_value = "0")]
+		wait: Option<u64>,
+
+		/// Check the transaction against the node and stop before signing.
+		#[arg(long)]
+		dry_run: bool,
 	},
 
 	/// List badges held by an address.
@@ -179,14 +325,71 @@ pub enum BadgeCommand {
 
 	/// Verify whether a badge exists on-chain.
 	Verify {
-		/// Event ID (64-character hex string).
+		/// Event ID, alias, unique ID prefix, or event manifest path.
+		event_id: String,
+
+		/// Holder CKB address.
+		address: String,
+	},
+
+	/// Export a badge as a signed Open Badges 3.0 or W3C VC document.
+	Export {
+		/// Event ID, alias, unique ID prefix, or event manifest path.
 		event_id: String,
 
 		/// Holder CKB address.
 		address: String,
+
+		/// Credential format.
+		#[arg(long, default_value = "openbadges")]
+		format: CredentialFormat,
+
+		/// Write the document to a file instead of stdout.
+		#[arg(long)]
+		out: Option<PathBuf>,
+	},
+
+	/// Verify an exported credential document offline.
+	VerifyCredential {
+		/// Path to the credential JSON.
+		file: PathBuf,
+	},
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CredentialFormat {
+	Openbadges,
+	Vc,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum QrFormat {
+	/// `v2|event_id|timestamp|hmac|key=value|...`.
+	Pipe,
+	/// `<frontend_url>/a?e=<event_id>&t=<timestamp>&h=<hmac>&...&v=2`.
+	Url,
+}
+
+// -- Proof subcommands --
+
+#[derive(Subcommand)]
+pub enum ProofCommand {
+	/// Check a proof bundle against the chain and its signature.
+	Verify {
+		/// Path to a proof bundle written by `attend`.
+		file: PathBuf,
 	},
 }
 
+// -- Contracts subcommands --
+
+#[derive(Subcommand)]
+pub enum ContractsCommand {
+	/// Check each deploy cell is live and carries its Type-ID, and report
+	/// upgraded code.
+	Verify,
+}
+
 // -- Tx subcommands --
 
 #[derive(Subcommand)]
@@ -196,4 +399,24 @@ pub enum TxCommand {
 		/// Transaction hash (0x-prefixed).
 		tx_hash: String,
 	},
+
+	/// Fetch a transaction and decode its PoP outputs, capacities and fee.
+	Show {
+		/// Transaction hash (0x-prefixed).
+		tx_hash: String,
+	},
+
+	/// Wait until a transaction is committed and buried N blocks deep.
+	Wait {
+		/// Transaction hash (0x-prefixed).
+		tx_hash: String,
+
+		/// Blocks required on top of the commit block.
+		#[arg(long, default_value = "0")]
+		confirmations: u64,
+
+		/// Give up after this many seconds.
+		#[arg(long, default_value = "600")]
+		timeout: u64,
+	},
 }
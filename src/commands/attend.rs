@@ -1,70 +1,340 @@
+use std::path::Path;
+
 use anyhow::Result;
-use sha2::{Digest, Sha256};
 
+use crate::backend::{BackendClient, BackendError};
 use crate::cli::Cli;
-use crate::commands::{resolve_rpc, resolve_signer};
+use crate::commands::badge::{find_badge, report_minted};
+use crate::commands::tx::wait_for_tx;
+use crate::commands::{
+	check_delegated_issuer, preflight, remember_event, resolve_cell_deps, resolve_contracts, resolve_rpc,
+	resolve_signer,
+};
 use crate::config::Config;
-use crate::contracts::CONTRACTS;
+use crate::contracts::NetworkContracts;
 use crate::crypto::{self, QrPayload};
-use crate::rpc::RpcClient;
+use crate::proof::ProofBundle;
+use crate::queue::QueuedAttendance;
+use crate::rpc::{RpcClient, RpcError};
+use crate::signer::Signer;
 
-/// Full attendance pipeline: parse QR -> verify freshness -> sign
-/// attendance proof -> mint badge -> broadcast.
-pub async fn run(cli: &Cli, qr_data: &str) -> Result<()> {
+/// Full attendance pipeline: parse QR -> verify freshness -> check for an
+/// existing badge -> sign attendance proof -> queue it -> mint badge ->
+/// broadcast -> save proof bundle -> remember the event.  A mint that
+/// fails because the node or registry cannot be reached stays queued for
+/// [`flush`], and the command fails.
+pub async fn run(cli: &Cli, qr_data: &str, wait: Option<u64>, dry_run: bool) -> Result<()> {
 	let config = Config::load()?;
 	let network = cli.network.as_str();
-	let rpc_url = resolve_rpc(cli, &config);
-	let rpc = RpcClient::new(&rpc_url);
-	let contracts = CONTRACTS.for_network(network);
+	let rpc = resolve_rpc(cli, &config);
+	let contracts = &resolve_contracts(&config, network)?;
 
 	// 1. Parse QR payload.
 	let qr = QrPayload::parse(qr_data).ok_or_else(|| {
-		anyhow::anyhow!("Invalid QR data. Expected format: event_id|timestamp|hmac")
+		anyhow::anyhow!(
+			"Invalid QR data. Expected an attend link (https://ckb-pop.xyz/a?e=...&v=2), \
+			 v2|event_id|timestamp|hmac|..., or event_id|timestamp|hmac"
+		)
 	})?;
+	if let Some(qr_network) = qr.network.as_deref().filter(|&n| n != network) {
+		anyhow::bail!(
+			"This QR code is for an event on {qr_network}, but ckb-pop is using {network}. \
+			 Re-run with --network {qr_network}."
+		);
+	}
 	println!("Event:  {}", qr.event_id);
 	println!("QR ts:  {}", qr.timestamp);
-
-	// 2. Check freshness (must be within 60 seconds).
-	let now = chrono::Utc::now().timestamp();
-	let age = now - qr.timestamp;
-	if !(0..=60).contains(&age) {
-		anyhow::bail!("QR code expired ({age}s old, maximum is 60s).");
+	if let Some(issuer) = &qr.issuer {
+		println!("Issuer: {issuer} (co-organizer)");
 	}
 
-	// 3. Resolve signer and address.
+	// 2. Check freshness (must be within 60 seconds, and not from before
+	// its window opened).
+	qr.check_fresh(chrono::Utc::now().timestamp())?;
+
+	// 3. Resolve signer and address, and stop early if the badge exists.
+	// Only an unreachable node lets the attendance go on unchecked, so it
+	// can be signed and queued while the QR code is fresh.
 	let signer = resolve_signer(cli, &config)?;
 	let address = signer.address().to_owned();
 
-	// 4. Sign the attendance proof message.
+	match find_badge(&rpc, &contracts.dob_badge.code_hash, &qr.event_id, &address).await {
+		Ok(Some(cell)) => {
+			report_minted(&qr.event_id, &address, &cell);
+			return Ok(());
+		}
+		Ok(None) => {}
+		Err(e) if is_offline(&e) => {
+			eprintln!("Warning: could not check for an existing badge: {e}")
+		}
+		Err(e) => return Err(e.context("could not check for an existing badge")),
+	}
+
+	// Badges from a delegated window name the co-organizer who opened it.
+	let issuer = qr.issuer.clone().unwrap_or_else(|| address.clone());
+
+	// 4. With --dry-run, check the mint without any wallet prompt.  The
+	// proof hash is not known yet, but it only changes the content hash,
+	// not the shape.
+	if dry_run {
+		let contracts = &resolve_cell_deps(&rpc, network, contracts).await;
+		let preview = crate::tx_builder::build_badge_mint(
+			&contracts.dob_badge,
+			&qr.event_id,
+			&address,
+			recipient_lock(&address)?,
+			&issuer,
+			Some(&crypto::proof_hash("")),
+		)?;
+		preflight(cli, &rpc, contracts, &preview, &address, true).await?;
+		return Ok(());
+	}
+
+	// 5. Sign the attendance proof while the QR code is fresh, and queue it
+	// so a mint that fails on a bad connection can be retried later.
 	let msg = crypto::attendance_message(&qr.event_id, qr.timestamp, &address);
 	println!("Signing attendance proof...");
 	let sig = signer.sign_message(&msg).await?;
-	let proof_hash = hex::encode(Sha256::digest(sig.as_bytes()));
+	let attendance = QueuedAttendance {
+		version: QueuedAttendance::VERSION,
+		network: network.to_owned(),
+		event_id: qr.event_id.clone(),
+		qr_data: qr_data.to_owned(),
+		address: address.clone(),
+		issuer,
+		message: msg,
+		signature: sig,
+		signed_at: chrono::Utc::now().timestamp(),
+	};
+	let queued = match attendance.save() {
+		Ok(path) => Some(path),
+		Err(e) => {
+			eprintln!("Warning: could not queue the signed attendance: {e}");
+			None
+		}
+	};
 
-	// 5. Build the badge mint transaction.
-	let recipient_addr: ckb_sdk::Address = address
-		.parse()
-		.map_err(|e| anyhow::anyhow!("invalid address: {e}"))?;
-	let recipient_lock: ckb_types::packed::Script = (&recipient_addr).into();
+	// 6. Mint the badge and save the proof bundle.  A queued attendance
+	// still fails the command, so scripts can tell it from a minted badge.
+	let contracts = &resolve_cell_deps(&rpc, network, contracts).await;
+	let event_id = attendance.event_id.clone();
+	match mint(cli, &config, &rpc, contracts, signer.as_ref(), attendance).await {
+		Ok(bundle) => record(&rpc, bundle, &event_id, queued.as_deref(), wait).await?,
+		Err(e) => match queued.as_deref().filter(|_| is_offline(&e)) {
+			Some(path) => {
+				println!("Could not mint the badge: {e:#}");
+				println!("Your signed attendance is queued in {}.", path.display());
+				println!("Run `ckb-pop attend --flush` once you are back online.");
+				anyhow::bail!("badge not minted; the signed attendance is queued");
+			}
+			None => {
+				dequeue(queued.as_deref());
+				return Err(e);
+			}
+		},
+	}
+
+	// 7. Remember the event so later commands can refer to it by alias.
+	// The registry knows its name; events it does not know get no alias.
+	let name = match config.backend(network) {
+		Ok(backend) => BackendClient::new(&backend).event_metadata(&qr.event_id).await.ok(),
+		Err(_) => None,
+	}
+	.map(|m| m.name);
+	if let Some(alias) = remember_event(network, &qr.event_id, name.as_deref()) {
+		println!("  Alias: {alias}");
+	}
+
+	Ok(())
+}
+
+/// Mint the badges of attendances queued on the current network.  Each
+/// one keeps the signature made at the venue, so the QR code does not
+/// have to be fresh any more.
+pub async fn flush(cli: &Cli, wait: Option<u64>) -> Result<()> {
+	let config = Config::load()?;
+	let network = cli.network.as_str();
+	let queue = QueuedAttendance::list(network)?;
+	if queue.is_empty() {
+		println!("No queued attendance on {network}.");
+		return Ok(());
+	}
+
+	let rpc = resolve_rpc(cli, &config);
+	let contracts = resolve_contracts(&config, network)?;
+	let contracts = &resolve_cell_deps(&rpc, network, &contracts).await;
+	let signer = resolve_signer(cli, &config)?;
+	let address = signer.address().to_owned();
 
+	println!("{} queued attendance(s) on {network}.", queue.len());
+	let mut remaining = 0;
+	for (path, attendance) in queue {
+		println!();
+		println!("Event: {}", attendance.event_id);
+		if attendance.address != address {
+			println!("Skipped: signed by {}, not the current signer.", attendance.address);
+			remaining += 1;
+			continue;
+		}
+		let event_id = attendance.event_id.clone();
+		// A badge minted from this entry (say, by a run whose bundle was not
+		// written) becomes its proof bundle; the entry is never just dropped.
+		match find_badge(&rpc, &contracts.dob_badge.code_hash, &event_id, &address).await {
+			Ok(Some(badge)) if attendance.minted_as(badge.data()) => {
+				println!("Badge already minted in tx {}.", badge.tx_hash());
+				let bundle = attendance.into_bundle(badge.tx_hash(), None);
+				if let Err(e) = record(&rpc, bundle, &event_id, Some(&path), wait).await {
+					eprintln!("Warning: {e:#}");
+				}
+				continue;
+			}
+			Ok(Some(badge)) => {
+				println!(
+					"A badge for this event was already minted in tx {} from another proof, \
+					 so this one cannot be minted.",
+					badge.tx_hash()
+				);
+				println!("Kept in {}; delete it once it is no longer needed.", path.display());
+				remaining += 1;
+				continue;
+			}
+			Ok(None) => {}
+			Err(e) => eprintln!("Warning: could not check for an existing badge: {e}"),
+		}
+		match mint(cli, &config, &rpc, contracts, signer.as_ref(), attendance).await {
+			Ok(bundle) => {
+				if let Err(e) = record(&rpc, bundle, &event_id, Some(&path), wait).await {
+					eprintln!("Warning: {e:#}");
+				}
+				remember_event(network, &event_id, None);
+			}
+			Err(e) => {
+				println!("Still queued: {e:#}");
+				remaining += 1;
+			}
+		}
+	}
+
+	if remaining > 0 {
+		anyhow::bail!(
+			"{remaining} attendance(s) are still queued in {}",
+			QueuedAttendance::dir().display()
+		);
+	}
+	Ok(())
+}
+
+/// Build, sign and broadcast the badge mint for a signed attendance, and
+/// return its proof bundle.  A co-organizer named as issuer must hold a
+/// valid delegation from the event creator.
+async fn mint(
+	cli: &Cli,
+	config: &Config,
+	rpc: &RpcClient,
+	contracts: &NetworkContracts,
+	signer: &dyn Signer,
+	attendance: QueuedAttendance,
+) -> Result<ProofBundle> {
+	let address = &attendance.address;
+	if attendance.issuer != *address {
+		check_issuer(config, rpc, &attendance).await?;
+	}
 	let tx = crate::tx_builder::build_badge_mint(
 		&contracts.dob_badge,
-		&qr.event_id,
-		&address,
-		recipient_lock,
-		&address,
-		Some(&proof_hash),
+		&attendance.event_id,
+		address,
+		recipient_lock(address)?,
+		&attendance.issuer,
+		Some(&crypto::proof_hash(&attendance.signature)),
 	)?;
+	preflight(cli, rpc, contracts, &tx, address, false).await?;
 
-	// 6. Sign and broadcast.
 	println!("Signing badge transaction...");
 	let signed = signer.sign_transaction(tx).await?;
 
 	let json_tx = ckb_jsonrpc_types::TransactionView::from(signed);
-	let tx_hash = rpc.send_transaction(json_tx.inner)?;
+	let tx_hash = rpc.send_transaction(json_tx.inner).await?;
 
+	let tx_hash = format!("{tx_hash:#x}");
 	println!("Attendance recorded and badge minted!");
-	println!("  TX: {tx_hash:#x}");
+	println!("  TX: {tx_hash}");
+
+	Ok(attendance.into_bundle(tx_hash, None))
+}
+
+/// Keep the proof of a broadcast mint so presence can be shown without the
+/// backend.  The bundle is saved before waiting, so an interrupted wait
+/// cannot lose it; only then is the queue entry at `queued` dropped.  With
+/// `wait`, the block the mint lands in is added once it commits.
+async fn record(
+	rpc: &RpcClient,
+	mut bundle: ProofBundle,
+	event_id: &str,
+	queued: Option<&Path>,
+	wait: Option<u64>,
+) -> Result<()> {
+	match bundle.save(event_id) {
+		Ok(path) => {
+			println!("  Proof: {}", path.display());
+			dequeue(queued);
+		}
+		Err(e) => eprintln!("Warning: could not save proof bundle: {e}"),
+	}
+
+	let Some(confirmations) = wait else { return Ok(()) };
+	if let Some(committed) = wait_for_tx(rpc, &bundle.badge_tx_hash, confirmations, None).await? {
+		bundle.block_hash = Some(format!("{:#x}", committed.block_hash));
+		if let Err(e) = bundle.save(event_id) {
+			eprintln!("Warning: could not record the block in the proof bundle: {e}");
+		}
+	}
+	Ok(())
+}
 
+/// Check the co-organizer a queued attendance names as badge issuer.
+async fn check_issuer(config: &Config, rpc: &RpcClient, attendance: &QueuedAttendance) -> Result<()> {
+	let qr = QrPayload::parse(&attendance.qr_data)
+		.ok_or_else(|| anyhow::anyhow!("queued attendance holds an invalid QR payload"))?;
+	let issuer = &attendance.issuer;
+	let creator = check_delegated_issuer(
+		config,
+		rpc,
+		&attendance.network,
+		&attendance.event_id,
+		issuer,
+		qr.timestamp,
+	)
+	.await
+	.map_err(|e| e.context(format!("the QR code names co-organizer {issuer}")))?;
+	println!("Co-organizer {issuer} is delegated by the creator {creator}.");
 	Ok(())
 }
+
+/// Whether a failed mint is worth retrying from the queue: the node or
+/// the registry could not be reached.  Anything else, such as a rejected
+/// transaction or a refused signature, would fail the same way again.
+fn is_offline(err: &anyhow::Error) -> bool {
+	err.chain().any(|cause| {
+		matches!(cause.downcast_ref::<RpcError>(), Some(RpcError::Transport { .. }))
+			|| matches!(
+				cause.downcast_ref::<BackendError>(),
+				Some(BackendError::Transport { .. })
+			)
+	})
+}
+
+fn recipient_lock(address: &str) -> Result<ckb_types::packed::Script> {
+	let address: ckb_sdk::Address = address
+		.parse()
+		.map_err(|e| anyhow::anyhow!("invalid address: {e}"))?;
+	Ok((&address).into())
+}
+
+/// Drop a queue entry whose badge has been minted.
+fn dequeue(path: Option<&Path>) {
+	if let Some(path) = path {
+		if let Err(e) = std::fs::remove_file(path) {
+			eprintln!("Warning: could not remove {} from the queue: {e}", path.display());
+		}
+	}
+}
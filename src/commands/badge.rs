@@ -1,33 +1,59 @@
+use std::path::Path;
+
 use anyhow::Result;
 use sha2::{Digest, Sha256};
 
-use crate::cli::{BadgeCommand, Cli};
-use crate::commands::{resolve_rpc, resolve_signer};
+use crate::cli::{BadgeCommand, Cli, CredentialFormat};
+use crate::commands::tx::wait_for_tx;
+use crate::commands::{
+	event_anchor, preflight, resolve_cell_deps, resolve_contracts, resolve_event_id, resolve_rpc,
+	resolve_signer,
+};
 use crate::config::Config;
-use crate::contracts::CONTRACTS;
-use crate::crypto;
-use crate::rpc::RpcClient;
+use crate::credential::{self, BadgeEvidence, Format};
+use crate::crypto::{self, SignatureCheck};
+use crate::proof::ProofBundle;
+use crate::rpc::{IndexerCell, RpcClient};
 
 pub async fn run(cli: &Cli, cmd: &BadgeCommand) -> Result<()> {
 	let config = Config::load()?;
 	let network = cli.network.as_str();
-	let rpc_url = resolve_rpc(cli, &config);
-	let rpc = RpcClient::new(&rpc_url);
-	let contracts = CONTRACTS.for_network(network);
+	let rpc = resolve_rpc(cli, &config);
 
 	match cmd {
 		BadgeCommand::Verify { event_id, address } => {
-			verify_badge(&rpc, contracts.dob_badge.code_hash, event_id, address).await
+			let event_id = &resolve_event_id(network, event_id)?;
+			let contracts = resolve_contracts(&config, network)?;
+			verify_badge(&rpc, &contracts.dob_badge.code_hash, event_id, address).await
 		}
 		BadgeCommand::List { address } => {
-			list_badges(&rpc, contracts.dob_badge.code_hash, address).await
+			let contracts = resolve_contracts(&config, network)?;
+			list_badges(&rpc, &contracts.dob_badge.code_hash, address).await
+		}
+		BadgeCommand::Mint {
+			event_id,
+			to,
+			wait,
+			dry_run,
+		} => {
+			let event_id = &resolve_event_id(network, event_id)?;
+			mint_badge(cli, &config, &rpc, network, event_id, to, *wait, *dry_run).await
 		}
-		BadgeCommand::Mint { event_id, to } => {
-			mint_badge(cli, &config, &rpc, network, event_id, to).await
+		BadgeCommand::Export {
+			event_id,
+			address,
+			format,
+			out,
+		} => {
+			let event_id = &resolve_event_id(network, event_id)?;
+			export_badge(cli, &config, &rpc, network, event_id, address, *format, out.as_deref())
+				.await
 		}
+		BadgeCommand::VerifyCredential { file } => verify_credential(file),
 	}
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn mint_badge(
 	cli: &Cli,
 	config: &Config,
@@ -35,10 +61,18 @@ async fn mint_badge(
 	network: &str,
 	event_id: &str,
 	to: &str,
+	wait: Option<u64>,
+	dry_run: bool,
 ) -> Result<()> {
 	let signer = resolve_signer(cli, config)?;
 	let issuer = signer.address().to_owned();
-	let contracts = CONTRACTS.for_network(network);
+	let contracts = &resolve_contracts(config, network)?;
+
+	if already_minted(rpc, &contracts.dob_badge.code_hash, event_id, to).await {
+		return Ok(());
+	}
+
+	let contracts = &resolve_cell_deps(rpc, network, contracts).await;
 
 	let recipient_addr: ckb_sdk::Address = to
 		.parse()
@@ -54,82 +88,232 @@ async fn mint_badge(
 		None,
 	)?;
 
+	if !preflight(cli, rpc, contracts, &tx, &issuer, dry_run).await? {
+		return Ok(());
+	}
+
 	println!("Signing badge transaction...");
 	let signed = signer.sign_transaction(tx).await?;
 
 	let json_tx = ckb_jsonrpc_types::TransactionView::from(signed);
-	let tx_hash = rpc.send_transaction(json_tx.inner)?;
+	let tx_hash = rpc.send_transaction(json_tx.inner).await?;
 	println!("Badge minted for event {event_id}.");
 	println!("  Recipient: {to}");
 	println!("  TX: {tx_hash:#x}");
 
+	if let Some(confirmations) = wait {
+		wait_for_tx(rpc, &format!("{tx_hash:#x}"), confirmations, None).await?;
+	}
+
 	Ok(())
 }
 
+/// Check for an existing badge before the mint is signed.  Returns `true`
+/// (after saying so) when `address` already holds the badge, so callers
+/// can exit successfully and retried scripts stay idempotent.  A failed
+/// lookup only warns: the chain still rejects a real duplicate.
+pub async fn already_minted(
+	rpc: &RpcClient,
+	badge_code_hash: &str,
+	event_id: &str,
+	address: &str,
+) -> bool {
+	match find_badge(rpc, badge_code_hash, event_id, address).await {
+		Ok(Some(cell)) => {
+			report_minted(event_id, address, &cell);
+			true
+		}
+		Ok(None) => false,
+		Err(e) => {
+			eprintln!("Warning: could not check for an existing badge: {e}");
+			false
+		}
+	}
+}
+
+/// Say that `address` already holds the badge for `event_id` in `cell`.
+pub fn report_minted(event_id: &str, address: &str, cell: &IndexerCell) {
+	println!("Badge already minted for event {event_id} in tx {}.", cell.tx_hash());
+	println!("  Holder: {address}");
+	println!("Nothing to do.");
+}
+
 async fn verify_badge(
 	rpc: &RpcClient,
 	badge_code_hash: &str,
 	event_id: &str,
 	address: &str,
 ) -> Result<()> {
+	match find_badge(rpc, badge_code_hash, event_id, address).await? {
+		Some(cell) => {
+			println!("Badge EXISTS for event {event_id}");
+			println!("  Holder:  {address}");
+			println!("  Mint tx: {}", cell.tx_hash());
+		}
+		None => println!("No badge found for event {event_id}, address {address}."),
+	}
+
+	Ok(())
+}
+
+/// Look up the badge cell for an exact `(event_id, address)` pair.
+pub async fn find_badge(
+	rpc: &RpcClient,
+	badge_code_hash: &str,
+	event_id: &str,
+	address: &str,
+) -> Result<Option<IndexerCell>> {
 	let args = crypto::build_type_script_args(event_id, address);
-	let args_hex = format!("0x{}", hex::encode(&args));
-
-	let search_key = serde_json::json!({
-		"script": {
-			"code_hash": badge_code_hash,
-			"hash_type": "type",
-			"args": args_hex
-		},
-		"script_type": "type",
-		"script_search_mode": "exact",
-		"with_data": true
-	});
-
-	let page = rpc.get_cells(search_key, "asc", 1, None).await?;
-	let cells = page
-		.get("objects")
-		.and_then(|v| v.as_array())
-		.map(|a| a.len())
-		.unwrap_or(0);
-
-	if cells > 0 {
-		let cell = &page["objects"][0];
-		let tx = cell
-			.pointer("/out_point/tx_hash")
-			.and_then(|v| v.as_str())
-			.unwrap_or("unknown");
-		println!("Badge EXISTS for event {event_id}");
-		println!("  Holder:  {address}");
-		println!("  Mint tx: {tx}");
-	} else {
-		println!("No badge found for event {event_id}, address {address}.");
+	rpc.find_exact_type_cell(badge_code_hash, args).await
+}
+
+/// Turn an on-chain badge into a credential document, sealed with a
+/// signature from the configured signer (the holder or the event creator).
+#[allow(clippy::too_many_arguments)]
+async fn export_badge(
+	cli: &Cli,
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event_id: &str,
+	holder: &str,
+	format: CredentialFormat,
+	out: Option<&Path>,
+) -> Result<()> {
+	let contracts = &resolve_contracts(config, network)?;
+
+	let badge = find_badge(rpc, &contracts.dob_badge.code_hash, event_id, holder)
+		.await?
+		.ok_or_else(|| anyhow::anyhow!("no badge found for event {event_id}, address {holder}"))?;
+	let tx_hash = badge.tx_hash();
+
+	// The creator's anchor names the issuer and commits to the event metadata.
+	let (issuer, anchor) = event_anchor(config, rpc, network, event_id).await?;
+	let anchor_json = anchor.json_data().unwrap_or_default();
+
+	let mint_block = rpc
+		.get_transaction(&tx_hash)
+		.await?
+		.and_then(|t| t.tx_status.block_hash);
+	let minted_at = match mint_block {
+		Some(block_hash) => rpc
+			.get_header(&format!("{block_hash:#x}"))
+			.await?
+			.and_then(|h| {
+				chrono::DateTime::from_timestamp_millis(u64::from(h.inner.timestamp) as i64)
+			})
+			.map(|t| t.to_rfc3339())
+			.ok_or_else(|| anyhow::anyhow!("could not fetch the mint block header"))?,
+		None => anyhow::bail!("badge tx {tx_hash} is not committed yet"),
+	};
+
+	let signer = resolve_signer(cli, config)?;
+	let signer_addr = signer.address().to_owned();
+	if signer_addr != holder && signer_addr != issuer {
+		anyhow::bail!("only the holder or the event creator ({issuer}) can sign this credential");
+	}
+
+	let data = badge.data();
+	let minters = [issuer.as_str(), holder];
+	let (badge_issuer, proof_hash) =
+		badge_content(data, event_id, &tx_hash, minters).ok_or_else(|| {
+			anyhow::anyhow!(
+				"could not tell what badge {tx_hash} commits to: it was not minted by {}, and no proof \
+				 bundle in {} records it",
+				minters.join(" or "),
+				ProofBundle::dir().display()
+			)
+		})?;
+
+	let evidence = BadgeEvidence {
+		network: network.to_owned(),
+		event_id: event_id.to_owned(),
+		holder: holder.to_owned(),
+		issuer: issuer.clone(),
+		badge_issuer,
+		proof_hash,
+		tx_hash,
+		index: badge.out_point.index.value(),
+		type_args: format!("0x{}", hex::encode(badge.type_args().unwrap_or_default())),
+		cell_data: format!("0x{}", hex::encode(data)),
+		anchor_tx_hash: Some(anchor.tx_hash()),
+		anchor_type_args: format!("0x{}", hex::encode(anchor.type_args().unwrap_or_default())),
+		metadata_hash: anchor_json["metadata_hash"].as_str().map(str::to_owned),
+		minted_at,
+	};
+	let format = match format {
+		CredentialFormat::Openbadges => Format::OpenBadges,
+		CredentialFormat::Vc => Format::Vc,
+	};
+	let mut doc = credential::build(format, &evidence);
+	eprintln!("Signing credential...");
+	let sig = signer.sign_message(&credential::signing_message(&doc)?).await?;
+	credential::attach_proof(&mut doc, &signer_addr, &sig, &chrono::Utc::now().to_rfc3339());
+
+	let rendered = serde_json::to_string_pretty(&doc)?;
+	match out {
+		Some(path) => {
+			std::fs::write(path, rendered)?;
+			eprintln!("Credential written to {}", path.display());
+		}
+		None => println!("{rendered}"),
+	}
+
+	Ok(())
+}
+
+/// Work out the issuer and proof hash a badge's content hash covers, so a
+/// verifier can recompute its cell data.  Badges from `badge mint` carry
+/// no proof hash and name one of `minters` (the creator or the holder); badges from `attend` are found
+/// through the attendee's proof bundle.
+fn badge_content(
+	data: &[u8],
+	event_id: &str,
+	tx_hash: &str,
+	minters: [&str; 2],
+) -> Option<(String, Option<String>)> {
+	let matches = |issuer: &str, proof_hash: Option<&str>| {
+		crypto::build_badge_cell_data(event_id, issuer, proof_hash) == data
+	};
+	if let Some(minter) = minters.into_iter().find(|m| matches(m, None)) {
+		return Some((minter.to_owned(), None));
+	}
+	let bundle = ProofBundle::find_by_tx(tx_hash)?;
+	let proof_hash = crypto::proof_hash(&bundle.signature);
+	matches(&bundle.issuer, Some(&proof_hash)).then_some((bundle.issuer, Some(proof_hash)))
+}
+
+fn verify_credential(path: &Path) -> Result<()> {
+	let content = std::fs::read_to_string(path)?;
+	let doc: serde_json::Value = serde_json::from_str(&content)?;
+
+	match credential::verify(&doc)? {
+		SignatureCheck::Valid => println!("Credential VERIFIED: evidence and signature are valid."),
+		SignatureCheck::Invalid => anyhow::bail!("credential signature is invalid"),
+		SignatureCheck::Unsupported(lock) => println!(
+			"Evidence is consistent, but the signature uses an unsupported lock ({lock})."
+		),
 	}
 
 	Ok(())
 }
 
 async fn list_badges(rpc: &RpcClient, badge_code_hash: &str, address: &str) -> Result<()> {
-	let addr_hash = hex::encode(&Sha256::digest(address.as_bytes())[..20]);
-	let cells = rpc.find_all_badges(badge_code_hash).await?;
+	let addr_hash = Sha256::digest(address.as_bytes());
+	let mut cells = rpc.find_all_badges(badge_code_hash)?;
 
 	let mut count = 0u32;
-	for cell in &cells {
-		let args = match cell.pointer("/output/type/args").and_then(|v| v.as_str()) {
-			Some(a) => a.strip_prefix("0x").unwrap_or(a),
-			None => continue,
+	while let Some(cell) = cells.next().await? {
+		let Some(args) = cell.type_args() else {
+			continue;
 		};
-		if args.len() < 80 || args[40..80] != addr_hash {
+		if args.len() < 40 || args[20..40] != addr_hash[..] {
 			continue;
 		}
 
 		count += 1;
-		let event_hash = &args[..40];
-		let tx = cell
-			.pointer("/out_point/tx_hash")
-			.and_then(|v| v.as_str())
-			.unwrap_or("unknown");
-		println!("#{count}  event_hash={event_hash}  tx={tx}");
+		let event_hash = hex::encode(&args[..20]);
+		println!("#{count}  event_hash={event_hash}  tx={}", cell.tx_hash());
 	}
 
 	if count == 0 {
@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+
+use crate::cli::{Cli, ContractsCommand};
+use crate::commands::{resolve_contracts, resolve_rpc};
+use crate::config::Config;
+use crate::contracts::{self, DepType, NetworkContracts};
+use crate::rpc::RpcClient;
+
+pub async fn run(cli: &Cli, cmd: &ContractsCommand) -> Result<()> {
+	let config = Config::load()?;
+	let network = cli.network.as_str();
+	let rpc = resolve_rpc(cli, &config);
+	let contracts = resolve_contracts(&config, network)?;
+
+	match cmd {
+		ContractsCommand::Verify => {
+			println!("Verifying PoP contracts on {network}...");
+			let problems = verify_deployments(&rpc, &contracts, true).await?;
+			println!();
+			if !problems.is_empty() {
+				anyhow::bail!("{} problem(s) with the {network} deployment", problems.len());
+			}
+			println!("All contracts carry their configured Type-ID.");
+			Ok(())
+		}
+	}
+}
+
+/// Check both contracts' deploy cells against the registry and return
+/// every problem found, each prefixed with the contract name.  A contract
+/// is identified by its Type-ID args; code that differs from the pinned
+/// data hash, or that moved off a spent deploy cell, is reported as an
+/// upgrade.  With `verbose`, print what was found for each contract.
+pub async fn verify_deployments(
+	rpc: &RpcClient,
+	contracts: &NetworkContracts,
+	verbose: bool,
+) -> Result<Vec<String>> {
+	let mut all = Vec::new();
+	for (name, info) in [
+		("dob-badge", &contracts.dob_badge),
+		("event-anchor", &contracts.event_anchor),
+	] {
+		let args = contracts::type_id_args(rpc, info)
+			.await
+			.with_context(|| format!("{name}: could not read the Type-ID"))?;
+		let mut at = info.clone();
+		let mut report = contracts::inspect(rpc, info).await?;
+		let mut upgrades = Vec::new();
+		if !report.live && info.dep_type == DepType::Code {
+			// An upgrade spends the deploy cell; follow the Type-ID to the new one.
+			let type_id = format!("{:#x}", ckb_sdk::constants::TYPE_ID_CODE_HASH);
+			if let Some(cell) = rpc.find_exact_type_cell(&type_id, args.clone()).await? {
+				at = info.at(&cell.tx_hash(), cell.out_point.index.value());
+				report = contracts::inspect(rpc, &at).await?;
+				upgrades.push(format!(
+					"code cell moved to {}:{}",
+					at.deploy_tx_hash, at.deploy_out_index
+				));
+			}
+		}
+		let args = format!("0x{}", hex::encode(&args));
+		let problems = report.problems(&at, &args);
+		upgrades.extend(report.upgrade(info));
+
+		if verbose {
+			println!();
+			println!("{name}");
+			println!("  Deploy cell: {}:{}", at.deploy_tx_hash, at.deploy_out_index);
+			if let Some(binary) = report.binary_out_point.as_ref().filter(|b| {
+				**b != format!("{}:{}", at.deploy_tx_hash, at.deploy_out_index)
+			}) {
+				println!("  Binary cell: {binary}");
+			}
+			println!("  Code hash:   {}", info.code_hash);
+			println!("  Type-ID:     {args}");
+			println!("  Data hash:   {}", report.data_hash.as_deref().unwrap_or(&info.data_hash));
+			if problems.is_empty() {
+				println!("  [ok] live and carries the Type-ID");
+			}
+			for upgrade in &upgrades {
+				println!("  [up] {upgrade}");
+			}
+			for problem in &problems {
+				println!("  [!!] {problem}");
+			}
+		} else {
+			for upgrade in &upgrades {
+				eprintln!("Note: {name} {upgrade}.");
+			}
+		}
+		all.extend(problems.into_iter().map(|p| format!("{name}: {p}")));
+	}
+	Ok(all)
+}
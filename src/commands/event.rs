@@ -1,56 +1,157 @@
-use std::io::Write as _;
+use std::path::Path;
 
 use anyhow::Result;
 use sha2::{Digest, Sha256};
 
-use crate::cli::{Cli, EventCommand};
-use crate::commands::{resolve_rpc, resolve_signer};
+use crate::alias::{self, EventAliases};
+use crate::backend::{BackendClient, CreateEventRequest};
+use crate::cli::{Cli, EventCommand, QrFormat};
+use crate::commands::tx::wait_for_tx;
+use crate::commands::{
+	event_anchor, event_creator, find_delegation, preflight, remember_event, resolve_cell_deps,
+	resolve_contracts, resolve_event_id, resolve_rpc, resolve_signer,
+};
 use crate::config::Config;
-use crate::contracts::CONTRACTS;
-use crate::crypto;
+use crate::contracts::NetworkContracts;
+use crate::crypto::{self, QrPayload};
+use crate::delegation::Delegation;
+use crate::event::{
+	anchor_creator, rfc3339, EventDescriptor, EventMetadata, MetadataFormat, PendingActivation,
+	PendingActivations,
+};
+use crate::manifest::{EventManifest, ManifestEvent, ManifestState};
 use crate::rpc::RpcClient;
+use crate::schedule::{self, Phase, ScheduleFile, SignedSchedule, Window};
+use crate::signer::Signer;
 
-/// Backend URL for the ckb-pop.xyz event registry.
-const BACKEND_URL: &str = "https://ckb-pop-backend.fly.dev/api";
+/// How long `event create` waits for the anchor to commit before handing
+/// activation back to the user.
+const ACTIVATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(90);
 
-/// Public URL for viewing and managing events.
-const FRONTEND_URL: &str = "https://ckb-pop.xyz";
+/// Window length when neither `--duration` nor a manifest window sets one.
+const DEFAULT_WINDOW_MINUTES: u64 = 60;
 
 pub async fn run(cli: &Cli, cmd: &EventCommand) -> Result<()> {
 	let config = Config::load()?;
 	let network = cli.network.as_str();
-	let rpc_url = resolve_rpc(cli, &config);
-	let rpc = RpcClient::new(&rpc_url);
-	let contracts = CONTRACTS.for_network(network);
+	let rpc = resolve_rpc(cli, &config);
 
 	match cmd {
-		EventCommand::Show { event_id } => {
-			show_event(&rpc, contracts.event_anchor.code_hash, event_id).await
+		EventCommand::Show { event_id, metadata } => {
+			let event_id = &resolve_event_id(network, event_id)?;
+			show_event(&config, &rpc, network, event_id, metadata.as_deref()).await
 		}
 		EventCommand::List { creator } => {
-			list_events(&rpc, contracts.event_anchor.code_hash, creator.as_deref()).await
+			let contracts = resolve_contracts(&config, network)?;
+			list_events(&rpc, &contracts.event_anchor.code_hash, creator.as_deref()).await
 		}
 		EventCommand::Create {
+			from,
 			name,
 			description,
 			image_url,
 			location,
 			start,
 			end,
+			wait,
+			dry_run,
+			no_backend,
 		} => {
+			// Reject bad input before anything touches the chain or the wallet.
+			let metadata = match from {
+				Some(path) => EventManifest::load(path)?.metadata()?,
+				None => EventMetadata::from_input(
+					name.as_deref().unwrap_or_default(),
+					description.as_deref().unwrap_or_default(),
+					image_url.as_deref(),
+					location.as_deref(),
+					start.as_deref(),
+					end.as_deref(),
+				)?,
+			};
 			create_event(
-				cli, &config, &rpc, network, name, description,
-				image_url.as_deref(),
-				location.as_deref(),
-				start.as_deref(),
-				end.as_deref(),
+				cli, &config, &rpc, network, metadata,
+				from.as_deref(),
+				*wait,
+				*dry_run,
+				*no_backend,
 			)
 			.await
 		}
+		EventCommand::Window {
+			event_id,
+			schedule: Some(path),
+			delegation,
+			qr_format,
+			..
+		} => {
+			let link_base = qr_link_base(&config, network, *qr_format)?;
+			let windows = schedule_windows(event_id, path)?;
+			let event_id = &resolve_event_id(network, event_id)?;
+			let organizer =
+				resolve_organizer(cli, &config, &rpc, network, event_id, delegation.as_deref())
+					.await?;
+			run_schedule(&organizer, network, event_id, &windows, link_base.as_deref()).await
+		}
 		EventCommand::Window {
 			event_id,
 			duration,
-		} => open_window(cli, &config, event_id, *duration).await,
+			schedule: None,
+			delegation,
+			qr_format,
+		} => {
+			let link_base = qr_link_base(&config, network, *qr_format)?;
+			let duration = match duration {
+				Some(minutes) => *minutes,
+				None => current_window_minutes(event_id)?.unwrap_or(DEFAULT_WINDOW_MINUTES),
+			};
+			let event_id = &resolve_event_id(network, event_id)?;
+			let organizer =
+				resolve_organizer(cli, &config, &rpc, network, event_id, delegation.as_deref())
+					.await?;
+			open_window(&organizer, network, event_id, duration, link_base.as_deref()).await
+		}
+		EventCommand::Delegate {
+			event_id,
+			to,
+			expires,
+		} => {
+			delegate_event(cli, &config, &rpc, network, event_id, to, expires.as_deref()).await
+		}
+		EventCommand::Alias { alias, event } => match (alias, event) {
+			(Some(alias), Some(event)) => {
+				let event_id = resolve_event_id(network, event)?;
+				let mut aliases = EventAliases::load()?;
+				aliases.set_alias(network, &event_id, alias)?;
+				aliases.save()?;
+				println!("{alias} -> {event_id}");
+				Ok(())
+			}
+			_ => list_aliases(network),
+		},
+		EventCommand::Activate { event_id, tx } => {
+			let event_id = &resolve_event_id(network, event_id)?;
+			let contracts = resolve_contracts(&config, network)?;
+			let backend = BackendClient::new(&config.backend(network)?);
+			let anchor_code_hash = &contracts.event_anchor.code_hash;
+			let creator = cli
+				.address
+				.as_deref()
+				.or(config.signer.address.as_deref())
+				.ok_or_else(|| {
+					anyhow::anyhow!("No address configured. Run: ckb-pop signer connect")
+				})?;
+			activate_event(
+				&rpc,
+				&backend,
+				network,
+				anchor_code_hash,
+				event_id,
+				creator,
+				tx.as_deref(),
+			)
+			.await
+		}
 	}
 }
 
@@ -60,87 +161,178 @@ async fn create_event(
 	config: &Config,
 	rpc: &RpcClient,
 	network: &str,
-	name: &str,
-	description: &str,
-	image_url: Option<&str>,
-	location: Option<&str>,
-	start: Option<&str>,
-	end: Option<&str>,
+	metadata: EventMetadata,
+	manifest: Option<&Path>,
+	wait: Option<u64>,
+	dry_run: bool,
+	no_backend: bool,
 ) -> Result<()> {
 	let signer = resolve_signer(cli, config)?;
 	let address = signer.address().to_owned();
-	let contracts = CONTRACTS.for_network(network);
+	let contracts = &resolve_cell_deps(rpc, network, &resolve_contracts(config, network)?).await;
 
 	// Show the creator address up front so users can verify it matches
 	// the wallet they will connect on ckb-pop.xyz.
 	println!("Creator address: {address}");
-	println!("Tip: connect this same address on ckb-pop.xyz to see this event in My Events.");
+	if !no_backend {
+		println!("Tip: connect this same address on ckb-pop.xyz to see this event in My Events.");
+	}
 	println!();
 
-	// Step 1: Sign the event-creation proof.
-	// The backend and website both use this message format to authenticate
-	// the creator before assigning a canonical event ID.
-	let nonce = gen_uuid_v4();
-	let create_msg = format!("CKB-PoP-CreateEvent|{nonce}");
-	println!("Signing event creation proof...");
-	let creator_sig = signer.sign_message(&create_msg).await?;
-
-	// Step 2: Register with the backend to get the canonical event ID.
-	let metadata_body = serde_json::json!({
-		"name": name,
-		"description": description,
-		"image_url": image_url,
-		"location": location,
-		"start_time": start,
-		"end_time": end,
-	});
-	let body = serde_json::json!({
-		"creator_address": address,
-		"creator_signature": creator_sig,
-		"nonce": nonce,
-		"metadata": metadata_body,
-	});
-
-	let http = reqwest::Client::new();
-	let resp = http
-		.post(format!("{BACKEND_URL}/events/create"))
-		.json(&body)
-		.send()
-		.await?;
+	let backend = match no_backend {
+		true => None,
+		false => Some(BackendClient::new(&config.backend(network)?)),
+	};
 
-	if !resp.status().is_success() {
-		let err: serde_json::Value = resp.json().await.unwrap_or_default();
-		anyhow::bail!(
-			"backend rejected event creation: {}",
-			err.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error")
-		);
+	// An earlier run may have registered or anchored this exact event and
+	// stopped before the backend recorded the anchor.  Finish that run
+	// instead of registering a duplicate.  Runs are matched on the hash of
+	// every metadata field, not just the ones the anchor commits to.
+	let details_hash = metadata.hash();
+	let mut registered = None;
+	if let Some(backend) = &backend {
+		let pending = PendingActivations::load()?;
+		if let Some(p) = pending.find_matching(network, &address, &details_hash) {
+			let broadcast = match &p.anchor_tx_hash {
+				Some(tx) => rpc.get_transaction(tx).await?.map(|_| tx.clone()),
+				None => None,
+			};
+			match broadcast {
+				Some(tx) => {
+					println!(
+						"Event {} was already created with these details (anchor TX {tx}).",
+						p.event_id
+					);
+					if dry_run {
+						println!("Dry run: its activation would be resumed.");
+						return Ok(());
+					}
+					println!("Resuming its activation.");
+					record_manifest_event(manifest, network, &p.event_id, &tx);
+					let anchor_code_hash = &contracts.event_anchor.code_hash;
+					return activate_event(
+						rpc,
+						backend,
+						network,
+						anchor_code_hash,
+						&p.event_id,
+						&address,
+						Some(&tx),
+					)
+					.await;
+				}
+				None => {
+					println!(
+						"Event {} was already registered with these details but never anchored.",
+						p.event_id
+					);
+					println!("Anchoring it under the same event ID.");
+					registered = Some(p.event_id.clone());
+				}
+			}
+		}
 	}
 
-	let result: serde_json::Value = resp.json().await?;
-	let event_id = result["event_id"]
-		.as_str()
-		.ok_or_else(|| anyhow::anyhow!("backend did not return event_id"))?
-		.to_owned();
-
-	// Step 3: Hash the metadata for the on-chain anchor cell.
-	// Use the same 4-key JSON structure the website uses so the hash matches.
-	let meta_for_hash = serde_json::json!({
-		"name": name,
-		"date": start,
-		"location": location,
-		"description": description,
-	});
-	let metadata_hash = hex::encode(Sha256::digest(
-		serde_json::to_string(&meta_for_hash)?.as_bytes(),
-	));
+	// A manifest stands for one event per network.
+	if let Some(path) = manifest {
+		if let Some(created) = ManifestState::load(path)?.get(network) {
+			anyhow::bail!(
+				"{} already created event {} on {network} (anchor TX {}); \
+				 remove it from {} to create another",
+				path.display(),
+				created.event_id,
+				created.anchor_tx_hash,
+				ManifestState::path(path).display()
+			);
+		}
+	}
 
-	// Step 4: Parse the creator's lock script from their address.
 	let ckb_addr: ckb_sdk::Address = address
 		.parse()
 		.map_err(|e| anyhow::anyhow!("invalid CKB address: {e}"))?;
 	let creator_lock: ckb_types::packed::Script = (&ckb_addr).into();
 
-	// Step 5: Build and sign the on-chain anchor transaction.
+	// Step 0: Dry-run an anchor before the wallet or backend is involved.
+	// The canonical event ID comes from the backend later; a locally
+	// derived one has the same length, so the transaction has the same shape.
+	// Without the backend, the local ID is the final one.
+	let nonce = gen_uuid_v4();
+	let local_id = crypto::compute_event_id(&address, chrono::Utc::now().timestamp(), &nonce);
+	let metadata_hash = metadata.anchor_hash(!no_backend);
+	let preview = crate::tx_builder::build_event_anchor(
+		&contracts.event_anchor,
+		&local_id,
+		&address,
+		creator_lock.clone(),
+		Some(&metadata_hash),
+	)?;
+	if dry_run {
+		println!("Metadata hash: {metadata_hash}");
+		if no_backend {
+			println!("Anchor transaction (unsigned):");
+		} else {
+			println!("Anchor transaction (unsigned; the registry assigns the final event ID):");
+		}
+		let json_tx = ckb_jsonrpc_types::TransactionView::from(preview.clone());
+		println!("{}", serde_json::to_string_pretty(&json_tx.inner)?);
+		println!();
+	}
+	if !preflight(cli, rpc, contracts, &preview, &address, dry_run).await? {
+		return Ok(());
+	}
+
+	let Some(backend) = backend else {
+		let event = LocalEvent {
+			network,
+			event_id: local_id,
+			address,
+			creator_lock,
+			metadata,
+			manifest,
+		};
+		return create_local_event(rpc, signer.as_ref(), contracts, event, wait).await;
+	};
+
+	// Step 1: Sign the event-creation proof.
+	// The backend and website both use this message format to authenticate
+	// the creator before assigning a canonical event ID.
+	let name = metadata.name.clone();
+	let event_id = match registered {
+		Some(event_id) => event_id,
+		None => {
+			let create_msg = format!("CKB-PoP-CreateEvent|{nonce}");
+			println!("Signing event creation proof...");
+			let creator_sig = signer.sign_message(&create_msg).await?;
+
+			// Step 2: Register with the backend to get the canonical event ID.
+			let request = CreateEventRequest {
+				creator_address: address.clone(),
+				creator_signature: creator_sig,
+				nonce,
+				metadata,
+			};
+			backend
+				.create_event(&request)
+				.await
+				.map_err(|e| e.context("backend rejected event creation"))?
+				.event_id
+		}
+	};
+
+	// Remember the event as soon as the backend knows it, so a run that
+	// stops anywhere from here on is resumed instead of registering a
+	// duplicate.
+	let mut record = PendingActivation {
+		network: network.to_owned(),
+		event_id: event_id.clone(),
+		anchor_tx_hash: None,
+		creator_address: address.clone(),
+		metadata_hash: details_hash,
+		created_at: chrono::Utc::now().timestamp(),
+	};
+	record_pending(&record);
+
+	// Step 3: Build and sign the on-chain anchor transaction.
 	let tx = crate::tx_builder::build_event_anchor(
 		&contracts.event_anchor,
 		&event_id,
@@ -151,82 +343,251 @@ async fn create_event(
 
 	println!("Signing transaction...");
 	let signed = signer.sign_transaction(tx).await?;
+	let tx_hash_str = format!("0x{}", hex::encode(signed.hash().raw_data()));
+	record.anchor_tx_hash = Some(tx_hash_str.clone());
+	record_pending(&record);
 
 	let json_tx = ckb_jsonrpc_types::TransactionView::from(signed);
-	let tx_hash = rpc.send_transaction(json_tx.inner)?;
-	let tx_hash_str = format!("{tx_hash:#x}");
+	rpc.send_transaction(json_tx.inner).await?;
 
 	println!("Event ID:  {event_id}");
+	if let Some(alias) = remember_event(network, &event_id, Some(&name)) {
+		println!("Alias:     {alias}");
+	}
 	println!("Anchor TX: {tx_hash_str}");
-	println!("View at:   {FRONTEND_URL}/events/{event_id}");
+	println!("View at:   {}", backend.event_url(&event_id));
+	record_manifest_event(manifest, network, &event_id, &tx_hash_str);
 	println!();
 
-	// Step 6: Wait for the anchor TX to be committed on-chain, then tell
+	// Step 4: Wait for the anchor TX to be committed on-chain, then tell
 	// the backend so it records the tx hash and shows the event as fully
 	// activated.  The event is already live in the backend registry; this
-	// step just adds on-chain proof.
-	if await_tx_confirmation(&http, &tx_hash_str).await {
-		activate_event_on_backend(&http, &event_id, &tx_hash_str).await;
+	// step just adds on-chain proof.  Without --wait, give up after ~90 s.
+	let timeout = match wait {
+		Some(_) => None,
+		None => Some(ACTIVATION_TIMEOUT),
+	};
+	if wait_for_tx(rpc, &tx_hash_str, wait.unwrap_or(0), timeout).await?.is_some() {
+		activate_event_on_backend(&backend, network, &event_id, &tx_hash_str).await;
 	} else {
-		println!("The event is live in the backend.  Run this command once the");
-		println!("TX is committed to store the anchor proof:");
-		println!("  curl -s -X POST {BACKEND_URL}/events/{event_id}/activate \\");
-		println!("       -H 'Content-Type: application/json' \\");
-		println!("       -d '{{\"tx_hash\":\"{tx_hash_str}\"}}'");
+		println!("The event is live in the backend.  Run this command to store");
+		println!("the anchor proof once the TX is committed:");
+		println!("  ckb-pop --network {network} event activate {event_id}");
 	}
 
 	Ok(())
 }
 
-/// Poll the backend tx-status endpoint until the anchor TX is committed
-/// on-chain.  Returns true on confirmation, false after ~90 s timeout.
-async fn await_tx_confirmation(http: &reqwest::Client, tx_hash: &str) -> bool {
-	print!("Waiting for anchor TX confirmation");
-	let _ = std::io::stdout().flush();
-
-	// Six attempts at 15-second intervals = 90 s total.
-	for i in 0..6u8 {
-		tokio::time::sleep(tokio::time::Duration::from_secs(15)).await;
-		let url = format!("{BACKEND_URL}/tx/{tx_hash}");
-		if let Ok(resp) = http.get(&url).send().await {
-			if let Ok(data) = resp.json::<serde_json::Value>().await {
-				if data["confirmed"].as_bool().unwrap_or(false) {
-					println!(" confirmed.");
-					return true;
-				}
-			}
+/// Resume activation of an anchored event: find its anchor transaction,
+/// wait for it to commit on-chain, and record it with the backend.  Only
+/// an anchor whose args commit to `creator` is taken from the chain, so
+/// someone else's anchor of the same event ID is never recorded.
+async fn activate_event(
+	rpc: &RpcClient,
+	backend: &BackendClient,
+	network: &str,
+	anchor_code_hash: &str,
+	event_id: &str,
+	creator: &str,
+	tx_hash: Option<&str>,
+) -> Result<()> {
+	let pending = PendingActivations::load()?
+		.find(network, event_id)
+		.and_then(|p| p.anchor_tx_hash.clone());
+	let tx_hash = match tx_hash.map(str::to_owned).or(pending) {
+		Some(hash) => hash,
+		None => {
+			let args = crypto::build_type_script_args(event_id, creator);
+			rpc.find_exact_type_cell(anchor_code_hash, args)
+				.await?
+				.filter(|cell| {
+					let data = cell.json_data().unwrap_or_default();
+					let args = cell.type_args().unwrap_or_default();
+					anchor_creator(event_id, args, &data).as_deref() == Some(creator)
+				})
+				.map(|cell| cell.tx_hash())
+				.ok_or_else(|| {
+					anyhow::anyhow!(
+						"no anchor by {creator} found on-chain for event {event_id}; pass --tx"
+					)
+				})?
 		}
-		if i < 5 {
-			print!(".");
-			let _ = std::io::stdout().flush();
+	};
+	println!("Anchor TX: {tx_hash}");
+
+	wait_for_tx(rpc, &tx_hash, 0, None).await?;
+	backend
+		.activate_event(event_id, &tx_hash)
+		.await
+		.map_err(|e| e.context("backend activation failed"))?;
+	forget_pending(network, event_id);
+	println!("Backend record updated with anchor TX hash.");
+	Ok(())
+}
+
+/// Save a pending activation, replacing the event's earlier entry.
+/// Best-effort: without it a stopped run cannot be resumed automatically.
+fn record_pending(record: &PendingActivation) {
+	let result = PendingActivations::load().and_then(|mut pending| {
+		pending.insert(record.clone());
+		pending.save()
+	});
+	if let Err(e) = result {
+		eprintln!("Warning: could not record the pending activation: {e}");
+	}
+}
+
+/// Drop a finished activation from the pending list.  Best-effort: a
+/// stale entry only makes `event create` resume an already-done activation.
+fn forget_pending(network: &str, event_id: &str) {
+	let result = PendingActivations::load().and_then(|mut pending| {
+		if pending.remove(network, event_id) {
+			pending.save()?;
 		}
+		Ok(())
+	});
+	if let Err(e) = result {
+		eprintln!("Warning: could not update the pending activation list: {e}");
 	}
+}
+
+/// An event whose ID was derived locally, ready to anchor.
+struct LocalEvent<'a> {
+	network: &'a str,
+	event_id: String,
+	address: String,
+	creator_lock: ckb_types::packed::Script,
+	metadata: EventMetadata,
+	/// Manifest the event was created from, if any.
+	manifest: Option<&'a Path>,
+}
+
+/// Anchor an event without the backend.  The full metadata is stored in a
+/// content-addressed file and its hash goes into the anchor; the printed
+/// descriptor carries both, so anyone can check one against the other.
+async fn create_local_event(
+	rpc: &RpcClient,
+	signer: &dyn Signer,
+	contracts: &NetworkContracts,
+	event: LocalEvent<'_>,
+	wait: Option<u64>,
+) -> Result<()> {
+	let metadata_hash = event.metadata.hash();
+	let metadata_path = event.metadata.store()?;
+
+	let tx = crate::tx_builder::build_event_anchor(
+		&contracts.event_anchor,
+		&event.event_id,
+		&event.address,
+		event.creator_lock,
+		Some(&metadata_hash),
+	)?;
+
+	println!("Signing transaction...");
+	let signed = signer.sign_transaction(tx).await?;
+
+	let json_tx = ckb_jsonrpc_types::TransactionView::from(signed);
+	let tx_hash = rpc.send_transaction(json_tx.inner).await?;
+	let tx_hash_str = format!("{tx_hash:#x}");
+
+	let descriptor = EventDescriptor {
+		version: EventDescriptor::VERSION,
+		network: event.network.to_owned(),
+		event_id: event.event_id,
+		creator_address: event.address,
+		anchor_tx_hash: tx_hash_str.clone(),
+		metadata_hash,
+		metadata: event.metadata,
+	};
+	let descriptor_path = descriptor.save()?;
+
+	println!("Event ID:   {}", descriptor.event_id);
+	let name = Some(descriptor.metadata.name.as_str());
+	if let Some(alias) = remember_event(event.network, &descriptor.event_id, name) {
+		println!("Alias:      {alias}");
+	}
+	println!("Anchor TX:  {tx_hash_str}");
+	println!("Metadata:   {}", metadata_path.display());
+	println!("Descriptor: {}", descriptor_path.display());
+	record_manifest_event(event.manifest, event.network, &descriptor.event_id, &tx_hash_str);
 	println!();
-	false
+	println!("Share this descriptor with attendees and verifiers:");
+	println!("{}", serde_json::to_string_pretty(&descriptor)?);
+
+	if let Some(confirmations) = wait {
+		println!();
+		wait_for_tx(rpc, &tx_hash_str, confirmations, None).await?;
+	}
+	Ok(())
+}
+
+/// Store the event ID next to the manifest it was created from, so later
+/// commands can take the manifest path instead.  Non-fatal: the ID is
+/// printed either way.
+fn record_manifest_event(manifest: Option<&Path>, network: &str, event_id: &str, tx_hash: &str) {
+	let Some(manifest) = manifest else {
+		return;
+	};
+	let created = ManifestEvent {
+		event_id: event_id.to_owned(),
+		anchor_tx_hash: tx_hash.to_owned(),
+		created_at: chrono::Utc::now().timestamp(),
+	};
+	match ManifestState::record(manifest, network, created) {
+		Ok(path) => println!("Event ID saved to {}.", path.display()),
+		Err(e) => eprintln!(
+			"Warning: could not save the event ID next to {}: {e}",
+			manifest.display()
+		),
+	}
+}
+
+/// Print the events known on `network`, with their aliases.
+fn list_aliases(network: &str) -> Result<()> {
+	let aliases = EventAliases::load()?;
+	let known: Vec<_> = aliases.events.iter().filter(|e| e.network == network).collect();
+	if known.is_empty() {
+		println!("No known events on {network}.");
+		return Ok(());
+	}
+	for event in known {
+		println!("{}", alias::describe(event));
+	}
+	Ok(())
+}
+
+/// Minutes left in the manifest window open right now, if `event` is a
+/// manifest path and one of its windows is open.
+fn current_window_minutes(event: &str) -> Result<Option<u64>> {
+	let path = Path::new(event);
+	if !EventManifest::is_manifest_path(path) {
+		return Ok(None);
+	}
+	let now = chrono::Utc::now();
+	let windows = EventManifest::load(path)?.windows()?;
+	Ok(windows
+		.iter()
+		.find(|w| w.start <= now && now < w.end)
+		.map(|w| ((w.end - now).num_seconds() as u64).div_ceil(60)))
 }
 
 /// POST the anchor TX hash to the backend activate endpoint so it records
-/// on-chain proof.  This is idempotent and non-fatal if it fails.
-async fn activate_event_on_backend(http: &reqwest::Client, event_id: &str, tx_hash: &str) {
-	let body = serde_json::json!({ "tx_hash": tx_hash });
-	match http
-		.post(format!("{BACKEND_URL}/events/{event_id}/activate"))
-		.json(&body)
-		.send()
-		.await
-	{
-		Ok(resp) if resp.status().is_success() => {
+/// on-chain proof.  This is idempotent and non-fatal if it fails; the
+/// activation then stays pending for `event activate`.
+async fn activate_event_on_backend(
+	backend: &BackendClient,
+	network: &str,
+	event_id: &str,
+	tx_hash: &str,
+) {
+	match backend.activate_event(event_id, tx_hash).await {
+		Ok(()) => {
+			forget_pending(network, event_id);
 			println!("Backend record updated with anchor TX hash.");
 		}
-		Ok(resp) => {
-			let err: serde_json::Value = resp.json().await.unwrap_or_default();
-			println!(
-				"Note: backend activation returned an error: {}",
-				err.get("error").and_then(|v| v.as_str()).unwrap_or("unknown")
-			);
-		}
 		Err(e) => {
-			println!("Note: could not reach backend to record anchor TX: {e}");
+			println!("Note: could not record the anchor TX with the backend: {e}");
+			println!("Retry with: ckb-pop --network {network} event activate {event_id}");
 		}
 	}
 }
@@ -244,16 +605,141 @@ fn gen_uuid_v4() -> String {
 	)
 }
 
+/// Whoever opens attendance windows: the configured signer, and the issuer
+/// to name in QR codes when that signer is a delegate of the creator.
+struct Organizer {
+	signer: Box<dyn Signer>,
+	issuer: Option<String>,
+}
+
+/// Work out whether the configured signer opens windows as the event
+/// creator or as a delegate.  A delegate needs a delegation signed by the
+/// event's creator: from `delegation`, the local store, or the registry.
+/// If the creator cannot be looked up, the signer is taken to be the
+/// creator, as before delegation existed.
+async fn resolve_organizer(
+	cli: &Cli,
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event_id: &str,
+	delegation: Option<&Path>,
+) -> Result<Organizer> {
+	let signer = resolve_signer(cli, config)?;
+	let address = signer.address().to_owned();
+	let creator = match event_creator(config, rpc, network, event_id).await {
+		Ok(creator) => creator,
+		Err(e) if delegation.is_none() => {
+			eprintln!("Warning: could not look up the event creator ({e}); opening as the creator.");
+			return Ok(Organizer { signer, issuer: None });
+		}
+		Err(e) => return Err(e.context("could not look up the event creator")),
+	};
+	if creator == address && delegation.is_none() {
+		return Ok(Organizer { signer, issuer: None });
+	}
+
+	let found = match delegation {
+		Some(path) => Some(Delegation::load(path)?),
+		None => find_delegation(config, network, event_id, &address).await?,
+	};
+	let Some(delegation) = found else {
+		anyhow::bail!(
+			"{address} is not the creator of event {event_id} ({creator}) and holds no \
+			 delegation; ask the creator to run: ckb-pop --network {network} event delegate \
+			 {event_id} --to {address}"
+		);
+	};
+	delegation.check(network, event_id, &creator, &address, chrono::Utc::now().timestamp())?;
+	println!("Opening windows as a co-organizer delegated by {creator}.");
+	Ok(Organizer { signer, issuer: Some(address) })
+}
+
+/// Sign delegations letting each of `to` (or, when empty, the manifest's
+/// co-organizers) open windows for the event.  Each is saved locally and
+/// published to the registry.
+async fn delegate_event(
+	cli: &Cli,
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event: &str,
+	to: &[String],
+	expires: Option<&str>,
+) -> Result<()> {
+	let delegates = if to.is_empty() {
+		let manifest = Path::new(event);
+		if !EventManifest::is_manifest_path(manifest) {
+			anyhow::bail!("pass --to <ADDRESS>, or the event manifest listing co_organizers");
+		}
+		EventManifest::load(manifest)?.co_organizers()?
+	} else {
+		to.to_vec()
+	};
+	if delegates.is_empty() {
+		anyhow::bail!("the manifest lists no co_organizers; pass --to <ADDRESS>");
+	}
+	for delegate in &delegates {
+		delegate
+			.parse::<ckb_sdk::Address>()
+			.map_err(|e| anyhow::anyhow!("invalid CKB address {delegate}: {e}"))?;
+	}
+	let expires_at = match expires {
+		Some(time) => {
+			let time = crate::event::parse_time("--expires", time)?;
+			if time <= chrono::Utc::now() {
+				anyhow::bail!("--expires must be in the future");
+			}
+			Some(time.timestamp())
+		}
+		None => None,
+	};
+
+	let event_id = resolve_event_id(network, event)?;
+	let creator = event_creator(config, rpc, network, &event_id).await?;
+	let signer = resolve_signer(cli, config)?;
+	if signer.address() != creator {
+		anyhow::bail!("only the event creator ({creator}) can delegate window opening");
+	}
+
+	let backend = BackendClient::new(&config.backend(network)?);
+	for delegate in delegates {
+		let mut delegation = Delegation {
+			version: Delegation::VERSION,
+			network: network.to_owned(),
+			event_id: event_id.clone(),
+			creator_address: creator.clone(),
+			delegate_address: delegate,
+			expires_at,
+			signature: String::new(),
+		};
+		println!("Signing delegation to {}...", delegation.delegate_address);
+		delegation.signature = signer.sign_message(&delegation.message()).await?;
+		// Co-organizers and verifiers refuse delegations they cannot check.
+		delegation.verify(chrono::Utc::now().timestamp())?;
+		let path = delegation.save()?;
+		println!("Delegation: {}", path.display());
+		match backend.publish_delegation(&delegation).await {
+			Ok(()) => println!("Published to the event registry."),
+			Err(e) => {
+				println!("Note: could not publish the delegation: {e}");
+				println!("Send the file to the co-organizer; they pass it with --delegation.");
+			}
+		}
+	}
+	Ok(())
+}
+
 /// Open an attendance window: sign the window message, then display
 /// rotating QR codes in the terminal until the window expires or the
 /// user interrupts with Ctrl-C.
 async fn open_window(
-	cli: &Cli,
-	config: &Config,
+	organizer: &Organizer,
+	network: &str,
 	event_id: &str,
 	duration_minutes: u64,
+	link_base: Option<&str>,
 ) -> Result<()> {
-	let signer = resolve_signer(cli, config)?;
 	let window_start = chrono::Utc::now().timestamp();
 	let window_end = if duration_minutes > 0 {
 		Some(window_start + (duration_minutes as i64) * 60)
@@ -263,7 +749,7 @@ async fn open_window(
 
 	let msg = crypto::window_message(event_id, window_start, window_end);
 	println!("Signing window proof...");
-	let creator_sig = signer.sign_message(&msg).await?;
+	let creator_sig = organizer.signer.sign_message(&msg).await?;
 
 	let window_secret = crypto::derive_window_secret(event_id, window_start, &creator_sig);
 
@@ -276,6 +762,7 @@ async fn open_window(
 	}
 	println!();
 
+	let qr = qr_template(organizer, network, event_id, window_start);
 	loop {
 		let now = chrono::Utc::now().timestamp();
 		if let Some(end) = window_end {
@@ -285,23 +772,7 @@ async fn open_window(
 			}
 		}
 
-		// Align to 30-second intervals.
-		let qr_ts = now - (now % 30);
-		let hmac = crypto::generate_qr_hmac(&window_secret, qr_ts);
-		let qr_data = format!("{event_id}|{qr_ts}|{hmac}");
-
-		// Clear screen and render QR.
-		print!("\x1B[2J\x1B[H");
-		let code = qrcode::QrCode::new(&qr_data)?;
-		let rendered = code
-			.render::<char>()
-			.quiet_zone(false)
-			.module_dimensions(2, 1)
-			.build();
-		println!("{rendered}");
-		println!();
-		println!("QR data: {qr_data}");
-		println!("Refreshes in {}s...", 30 - (now % 30));
+		show_qr(&qr, &window_secret, now, link_base)?;
 
 		tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 	}
@@ -309,26 +780,295 @@ async fn open_window(
 	Ok(())
 }
 
-// -- Read-only helpers (unchanged) --
+/// The QR payload fields that stay fixed for a window: which window it is,
+/// how often it rotates, the network, and the issuer when `organizer` is a
+/// delegate.
+fn qr_template(organizer: &Organizer, network: &str, event_id: &str, window_start: i64) -> QrPayload {
+	QrPayload {
+		version: QrPayload::VERSION,
+		event_id: event_id.to_owned(),
+		timestamp: 0,
+		hmac: String::new(),
+		issuer: organizer.issuer.clone(),
+		window_start: Some(window_start),
+		period: Some(crypto::QR_PERIOD),
+		network: Some(network.to_owned()),
+	}
+}
+
+/// Clear the screen and show the QR code for `now`, filling `template`
+/// with the timestamp and HMAC under `window_secret`.  With a `link_base`
+/// the QR holds a deep link phone cameras can open; the pipe form is
+/// printed below it for pasting into `ckb-pop attend`.
+fn show_qr(
+	template: &QrPayload,
+	window_secret: &[u8; 32],
+	now: i64,
+	link_base: Option<&str>,
+) -> Result<()> {
+	// Align to rotation intervals.
+	let period = i64::from(crypto::QR_PERIOD);
+	let qr_ts = now - (now % period);
+	let mut payload = QrPayload {
+		timestamp: qr_ts,
+		..template.clone()
+	};
+	payload.sign(window_secret);
+	let qr_data = payload.encode();
+	let link = link_base.map(|base| payload.to_url(base));
+
+	// Clear screen and render QR.
+	print!("\x1B[2J\x1B[H");
+	let code = qrcode::QrCode::new(link.as_deref().unwrap_or(&qr_data))?;
+	let rendered = code
+		.render::<char>()
+		.quiet_zone(false)
+		.module_dimensions(2, 1)
+		.build();
+	println!("{rendered}");
+	println!();
+	if let Some(link) = &link {
+		println!("Link:    {link}");
+	}
+	println!("QR data: {qr_data}");
+	println!("Refreshes in {}s...", period - (now % period));
+	Ok(())
+}
+
+/// Base URL for deep-link QR codes: the network's frontend, whose attend
+/// page takes the payload.  `None` for the pipe format.
+fn qr_link_base(config: &Config, network: &str, format: QrFormat) -> Result<Option<String>> {
+	Ok(match format {
+		QrFormat::Url => Some(config.backend(network)?.frontend_url),
+		QrFormat::Pipe => None,
+	})
+}
+
+/// The windows for `event window --schedule`: from the schedule file at
+/// `path`, or from the event manifest when `path` is empty.
+fn schedule_windows(event: &str, path: &str) -> Result<Vec<Window>> {
+	if !path.is_empty() {
+		return ScheduleFile::load(Path::new(path));
+	}
+	let manifest = Path::new(event);
+	if !EventManifest::is_manifest_path(manifest) {
+		anyhow::bail!(
+			"--schedule without a PATH reads the windows from the event manifest; \
+			 pass the manifest as the event"
+		);
+	}
+	let windows = EventManifest::load(manifest)?.windows()?;
+	if windows.is_empty() {
+		anyhow::bail!("{} lists no windows", manifest.display());
+	}
+	Ok(windows)
+}
+
+/// Run a schedule of attendance windows: sign it once up front, then show
+/// rotating QR codes while a window is open and a countdown to the next
+/// one otherwise.  Each window has its own secret, derived from the
+/// schedule signature and the window's start.
+async fn run_schedule(
+	organizer: &Organizer,
+	network: &str,
+	event_id: &str,
+	windows: &[Window],
+	link_base: Option<&str>,
+) -> Result<()> {
+	if schedule::phase(windows, chrono::Utc::now()) == Phase::Finished {
+		anyhow::bail!("every window in the schedule has already closed");
+	}
 
-async fn show_event(rpc: &RpcClient, anchor_code_hash: &str, event_id: &str) -> Result<()> {
-	let cells = rpc.find_event_anchors(anchor_code_hash, event_id).await?;
+	let bounds: Vec<(i64, i64)> = windows
+		.iter()
+		.map(|w| (w.start.timestamp(), w.end.timestamp()))
+		.collect();
+	let message = crypto::schedule_message(event_id, &bounds);
+	let signature = match SignedSchedule::load(network, event_id, &message)? {
+		Some(saved) => {
+			println!("Using the schedule signed earlier.");
+			saved.signature
+		}
+		None => {
+			println!("Signing schedule of {} windows...", windows.len());
+			let signature = organizer.signer.sign_message(&message).await?;
+			let signed = SignedSchedule {
+				network: network.to_owned(),
+				event_id: event_id.to_owned(),
+				message,
+				signature: signature.clone(),
+			};
+			if let Err(e) = signed.save() {
+				eprintln!("Warning: could not save the signed schedule: {e}");
+			}
+			signature
+		}
+	};
 
-	if cells.is_empty() {
+	let count = windows.len();
+	loop {
+		let now = chrono::Utc::now();
+		match schedule::phase(windows, now) {
+			Phase::Open { index, window } => {
+				let start = window.start.timestamp();
+				let secret = crypto::derive_window_secret(event_id, start, &signature);
+				let qr = qr_template(organizer, network, event_id, start);
+				show_qr(&qr, &secret, now.timestamp(), link_base)?;
+				println!(
+					"Window {}/{count} closes at {} (in {}).",
+					index + 1,
+					rfc3339(window.end),
+					countdown(window.end - now)
+				);
+			}
+			Phase::Waiting { index, window } => {
+				print!("\x1B[2J\x1B[H");
+				println!("Window {}/{count} opens at {}.", index + 1, rfc3339(window.start));
+				println!();
+				println!("Opens in {}  (Ctrl-C to stop)", countdown(window.start - now));
+			}
+			Phase::Finished => {
+				println!("All {count} windows have closed.");
+				break;
+			}
+		}
+
+		tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+	}
+
+	Ok(())
+}
+
+/// `1d 02:03:04`, or `02:03:04` under a day.
+fn countdown(remaining: chrono::Duration) -> String {
+	let secs = remaining.num_seconds().max(0);
+	let (days, secs) = (secs / 86_400, secs % 86_400);
+	let clock = format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60);
+	if days > 0 {
+		format!("{days}d {clock}")
+	} else {
+		clock
+	}
+}
+
+/// Print an event's anchor and check its metadata against it.  Anyone can
+/// anchor any event ID, so only the creator's anchor (see [`event_anchor`])
+/// is checked; anchors of the ID by other addresses are listed as foreign.
+async fn show_event(
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event_id: &str,
+	metadata_source: Option<&str>,
+) -> Result<()> {
+	let anchor_code_hash = &resolve_contracts(config, network)?.event_anchor.code_hash;
+	let mut cells = rpc.find_event_anchors(anchor_code_hash, event_id)?;
+	let mut foreign = Vec::new();
+	while let Some(cell) = cells.next().await? {
+		foreign.push(cell);
+	}
+	if foreign.is_empty() {
 		println!("No event anchor found for ID: {event_id}");
 		return Ok(());
 	}
 
-	for cell in &cells {
-		if let Some(json) = decode_cell_data(cell) {
-			println!("{}", serde_json::to_string_pretty(&json)?);
+	let anchored = event_anchor(config, rpc, network, event_id).await;
+	if let Ok((_, anchor)) = &anchored {
+		foreign.retain(|cell| cell.out_point != anchor.out_point);
+	}
+	for cell in &foreign {
+		println!("Foreign anchor tx: {} (not by the event creator; ignored)", cell.tx_hash());
+	}
+	let (creator, anchor) = anchored?;
+	if let Some(json) = anchor.json_data() {
+		println!("{}", serde_json::to_string_pretty(&json)?);
+	}
+	println!("Anchor tx: {}", anchor.tx_hash());
+	println!("Creator:   {creator}");
+	let metadata_hash = anchor
+		.json_data()
+		.and_then(|json| json.get("metadata_hash")?.as_str().map(str::to_owned));
+
+	// Fetch the details from wherever they were asked for: an explicit
+	// file or URL, a descriptor saved by `event create --no-backend`, or
+	// the registry.
+	let (metadata, source) = match metadata_source {
+		Some(source) => (load_metadata(source).await, source.to_owned()),
+		None => {
+			let descriptor = EventDescriptor::dir().join(format!("{event_id}.json"));
+			if descriptor.exists() {
+				let source = descriptor.display().to_string();
+				(load_metadata(&source).await, source)
+			} else {
+				let metadata = match config.backend(network) {
+					Ok(backend) => BackendClient::new(&backend).event_metadata(event_id).await,
+					Err(e) => Err(e),
+				};
+				(metadata, "the event registry".to_owned())
+			}
+		}
+	};
+	let metadata = match metadata {
+		Ok(m) => m,
+		Err(e) => {
+			println!();
+			println!("Metadata unavailable from {source}: {e:#}");
+			return Ok(());
+		}
+	};
+
+	println!();
+	println!("Name:        {}", metadata.name);
+	if let Some(start) = &metadata.start_time {
+		println!("Date:        {start}");
+	}
+	if let Some(end) = &metadata.end_time {
+		println!("Ends:        {end}");
+	}
+	if let Some(location) = &metadata.location {
+		println!("Location:    {location}");
+	}
+	if !metadata.description.is_empty() {
+		println!("Description: {}", metadata.description);
+	}
+	if let Some(image) = &metadata.image_url {
+		println!("Image:       {image}");
+	}
+	println!("Source:      {source}");
+
+	let Some(metadata_hash) = metadata_hash else {
+		println!("Metadata:    not verifiable (the anchor has no metadata_hash)");
+		return Ok(());
+	};
+	match metadata.matches(&metadata_hash) {
+		Some(format) => {
+			let note = match format {
+				MetadataFormat::Canonical => "",
+				MetadataFormat::LegacyWebsite => ", ckb-pop.xyz encoding",
+			};
+			println!("Metadata:    verified (matches the anchor's metadata_hash{note})");
+			Ok(())
 		}
-		if let Some(tx) = cell.pointer("/out_point/tx_hash").and_then(|v| v.as_str()) {
-			println!("Anchor tx: {tx}");
+		None => {
+			println!("Metadata:    MISMATCH");
+			println!("  anchored:  {metadata_hash}");
+			println!("  computed:  {}", metadata.hash());
+			anyhow::bail!("event metadata from {source} does not match the on-chain anchor")
 		}
 	}
+}
 
-	Ok(())
+/// Read event metadata from a JSON file or an http(s) URL.
+async fn load_metadata(source: &str) -> Result<EventMetadata> {
+	let is_url = source.starts_with("http://") || source.starts_with("https://");
+	let json: serde_json::Value = if is_url {
+		reqwest::get(source).await?.error_for_status()?.json().await?
+	} else {
+		let content = std::fs::read_to_string(source)
+			.map_err(|e| anyhow::anyhow!("could not read {source}: {e}"))?;
+		serde_json::from_str(&content)?
+	};
+	EventMetadata::from_json(json)
 }
 
 async fn list_events(
@@ -336,32 +1076,26 @@ async fn list_events(
 	anchor_code_hash: &str,
 	creator: Option<&str>,
 ) -> Result<()> {
-	let cells = rpc.find_all_event_anchors(anchor_code_hash).await?;
-	let creator_hash = creator.map(|a| hex::encode(&Sha256::digest(a.as_bytes())[..20]));
+	let mut cells = rpc.find_all_event_anchors(anchor_code_hash)?;
+	let creator_hash = creator.map(|a| Sha256::digest(a.as_bytes()));
 
 	let mut count = 0u32;
-	for cell in &cells {
+	while let Some(cell) = cells.next().await? {
 		if let Some(ref ch) = creator_hash {
-			let args = cell
-				.pointer("/output/type/args")
-				.and_then(|v| v.as_str())
-				.unwrap_or("");
-			let args = args.strip_prefix("0x").unwrap_or(args);
-			if args.len() >= 80 && args[40..80] != *ch {
+			let args = cell.type_args().unwrap_or_default();
+			if args.len() >= 40 && args[20..40] != ch[..20] {
 				continue;
 			}
 		}
 
 		count += 1;
 		print!("#{count}");
-		if let Some(json) = decode_cell_data(cell) {
+		if let Some(json) = cell.json_data() {
 			if let Some(id) = json.get("event_id").and_then(|v| v.as_str()) {
 				print!("  id={id}");
 			}
 		}
-		if let Some(tx) = cell.pointer("/out_point/tx_hash").and_then(|v| v.as_str()) {
-			print!("  tx={tx}");
-		}
+		print!("  tx={}", cell.tx_hash());
 		println!();
 	}
 
@@ -373,9 +1107,3 @@ async fn list_events(
 
 	Ok(())
 }
-
-fn decode_cell_data(cell: &serde_json::Value) -> Option<serde_json::Value> {
-	let hex_data = cell.pointer("/output_data").and_then(|v| v.as_str())?;
-	let raw = hex::decode(hex_data.strip_prefix("0x").unwrap_or(hex_data)).ok()?;
-	serde_json::from_slice(&raw).ok()
-}
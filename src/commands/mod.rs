@@ -1,19 +1,203 @@
 pub mod attend;
 pub mod badge;
+pub mod contracts;
 pub mod event;
+pub mod proof;
 pub mod signer;
 pub mod tx;
 
 use anyhow::Result;
 
+use crate::alias::EventAliases;
+use crate::backend::BackendClient;
 use crate::cli::{Cli, SignerArg};
 use crate::config::Config;
+use crate::contracts::{Contracts, NetworkContracts};
+use crate::delegation::Delegation;
+use crate::event::{anchor_creator, EventDescriptor};
+use crate::manifest::{EventManifest, ManifestState};
+use crate::preflight::{self, Verdict};
+use crate::rpc::{IndexerCell, RpcClient};
 
-/// Resolve the RPC URL from CLI flag or config.
-pub fn resolve_rpc(cli: &Cli, config: &Config) -> String {
-	cli.rpc_url
-		.clone()
-		.unwrap_or_else(|| config.rpc_url(cli.network.as_str()).to_owned())
+/// Build the RPC client from the CLI flag (a single pinned endpoint) or
+/// the configured endpoint list for the network.
+pub fn resolve_rpc(cli: &Cli, config: &Config) -> RpcClient {
+	let rpc = match &cli.rpc_url {
+		Some(url) => RpcClient::new(url),
+		None => RpcClient::with_endpoints(config.rpc_urls(cli.network.as_str())),
+	};
+	rpc.on_retry(warn_retry)
+}
+
+/// Tell the user a call is being retried after `endpoint` failed.
+pub fn warn_retry(endpoint: &str, err: &anyhow::Error) {
+	eprintln!("Warning: {endpoint} unreachable ({err}); retrying...");
+}
+
+/// Look up the PoP contract deployments for `network` in the registry
+/// (built-ins plus any defined in config).
+pub fn resolve_contracts(config: &Config, network: &str) -> Result<NetworkContracts> {
+	Ok(Contracts::load(config)?.for_network(network)?.clone())
+}
+
+/// Point each contract's cell dep at the live cell carrying its Type-ID,
+/// so a contract upgraded on-chain is picked up without a registry edit.
+/// Warns when that differs from the configured out point, and falls back
+/// to the configured one if the lookup fails.
+pub async fn resolve_cell_deps(
+	rpc: &RpcClient,
+	network: &str,
+	contracts: &NetworkContracts,
+) -> NetworkContracts {
+	let mut resolved = contracts.clone();
+	for (name, info) in [
+		("dob-badge", &mut resolved.dob_badge),
+		("event-anchor", &mut resolved.event_anchor),
+	] {
+		match crate::contracts::resolve_live(rpc, network, info).await {
+			Ok(live) => {
+				if live.deploy_tx_hash != info.deploy_tx_hash
+					|| live.deploy_out_index != info.deploy_out_index
+				{
+					eprintln!(
+						"Warning: {name} code cell has moved to {}:{} (configured {}:{}); \
+						 using the live cell.",
+						live.deploy_tx_hash,
+						live.deploy_out_index,
+						info.deploy_tx_hash,
+						info.deploy_out_index
+					);
+				}
+				*info = live;
+			}
+			Err(e) => eprintln!(
+				"Warning: could not resolve the live {name} code cell ({e}); using the configured one."
+			),
+		}
+	}
+	resolved
+}
+
+/// Turn an event argument into an event ID.  Every command that takes an
+/// event goes through here.  A path to an event manifest stands for the
+/// event created from it on `network`; anything else may be an alias, a
+/// full ID, or a unique prefix of a known event's ID.
+pub fn resolve_event_id(network: &str, event: &str) -> Result<String> {
+	let path = std::path::Path::new(event);
+	if !EventManifest::is_manifest_path(path) {
+		return EventAliases::load()?.resolve(network, event);
+	}
+	match ManifestState::load(path)?.get(network) {
+		Some(created) => Ok(created.event_id.clone()),
+		None => anyhow::bail!(
+			"no event has been created from {event} on {network}. Run: \
+			 ckb-pop --network {network} event create --from {event}"
+		),
+	}
+}
+
+/// An event's creator and their anchor of it.  Anyone can anchor any
+/// event ID under their own address, so the creator is not read from the
+/// chain: it comes from the descriptor saved by `event create --no-backend`
+/// or, failing that, the event registry, which only records creators that
+/// signed the creation request.  The chain must then hold an anchor of the
+/// event by that creator.
+pub async fn event_anchor(
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event_id: &str,
+) -> Result<(String, IndexerCell)> {
+	let creator = match EventDescriptor::load(network, event_id)? {
+		Some(descriptor) => descriptor.creator_address,
+		None => BackendClient::new(&config.backend(network)?)
+			.event_creator(event_id)
+			.await
+			.map_err(|e| e.context(format!("could not look up the creator of event {event_id}")))?,
+	};
+	let anchor_code_hash = resolve_contracts(config, network)?.event_anchor.code_hash;
+	let args = crate::crypto::build_type_script_args(event_id, &creator);
+	let anchor = rpc
+		.find_exact_type_cell(&anchor_code_hash, args)
+		.await?
+		.filter(|cell| {
+			let data = cell.json_data().unwrap_or_default();
+			let args = cell.type_args().unwrap_or_default();
+			anchor_creator(event_id, args, &data).as_deref() == Some(creator.as_str())
+		})
+		.ok_or_else(|| {
+			anyhow::anyhow!("event {event_id} has no on-chain anchor by its creator {creator}")
+		})?;
+	Ok((creator, anchor))
+}
+
+/// The creator of an event; see [`event_anchor`].
+pub async fn event_creator(
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event_id: &str,
+) -> Result<String> {
+	Ok(event_anchor(config, rpc, network, event_id).await?.0)
+}
+
+/// The delegation of `event_id` on `network` to `delegate`, from the local
+/// store or, failing that, the event registry if the network has one.
+pub async fn find_delegation(
+	config: &Config,
+	network: &str,
+	event_id: &str,
+	delegate: &str,
+) -> Result<Option<Delegation>> {
+	if let Some(found) = Delegation::find(network, event_id, delegate)? {
+		return Ok(Some(found));
+	}
+	let Ok(backend) = config.backend(network) else { return Ok(None) };
+	Ok(BackendClient::new(&backend)
+		.delegations(event_id)
+		.await
+		.unwrap_or_default()
+		.into_iter()
+		.find(|d| d.network == network && d.delegate_address == delegate))
+}
+
+/// Check that the co-organizer `issuer` named in a QR code was allowed to
+/// open windows for the event at `at`: it must be the event's creator (see
+/// [`event_anchor`]), or hold a delegation from that creator whose
+/// signature checks out.  Returns the creator.
+pub async fn check_delegated_issuer(
+	config: &Config,
+	rpc: &RpcClient,
+	network: &str,
+	event_id: &str,
+	issuer: &str,
+	at: i64,
+) -> Result<String> {
+	let creator = event_creator(config, rpc, network, event_id).await?;
+	if creator == issuer {
+		return Ok(creator);
+	}
+	let delegation = find_delegation(config, network, event_id, issuer)
+		.await?
+		.ok_or_else(|| {
+			anyhow::anyhow!("co-organizer {issuer} holds no delegation from the creator {creator}")
+		})?;
+	delegation.check(network, event_id, &creator, issuer, at)?;
+	Ok(creator)
+}
+
+/// Add an event to the alias table, aliased from `name` if it has no alias
+/// yet, and return its alias.  Best-effort: the full ID always works.
+pub fn remember_event(network: &str, event_id: &str, name: Option<&str>) -> Option<String> {
+	let result = EventAliases::load().and_then(|mut aliases| {
+		let alias = aliases.remember(network, event_id, name);
+		aliases.save()?;
+		Ok(alias)
+	});
+	result.unwrap_or_else(|e| {
+		eprintln!("Warning: could not update the event alias table: {e}");
+		None
+	})
 }
 
 /// Build a signer from CLI flags + config, failing if neither is set.
@@ -45,3 +229,61 @@ pub fn resolve_signer(
 	let network = cli.network.as_str();
 	crate::signer::from_method(&method, address.to_owned(), network)
 }
+
+/// Dry-run `tx` against the node before the wallet is prompted, paying
+/// from `payer`.  Fails if the node would reject the transaction, or, with
+/// `--verify-contracts`, if a contract deployment does not match the
+/// registry.  Returns `false` when `dry_run` is set and the command should
+/// stop here.
+///
+/// Outside `--dry-run` the checks are best-effort: if they cannot run (no
+/// indexer, unreachable node) the command carries on to the wallet.
+pub async fn preflight(
+	cli: &Cli,
+	rpc: &RpcClient,
+	contracts: &NetworkContracts,
+	tx: &ckb_types::core::TransactionView,
+	payer: &str,
+	dry_run: bool,
+) -> Result<bool> {
+	let network = cli.network.as_str();
+	if cli.verify_contracts {
+		match contracts::verify_deployments(rpc, contracts, false).await {
+			Ok(problems) if problems.is_empty() => {
+				println!("Pre-flight: contract deployments verified.")
+			}
+			Ok(problems) => anyhow::bail!(
+				"contract deployment does not match the registry:\n  {}",
+				problems.join("\n  ")
+			),
+			Err(e) if !dry_run => eprintln!("Warning: contract verification skipped: {e}"),
+			Err(e) => return Err(e.context("contract verification failed")),
+		}
+	}
+
+	let payer: ckb_sdk::Address = payer
+		.parse()
+		.map_err(|e| anyhow::anyhow!("invalid payer address: {e}"))?;
+	let verdict = match preflight::check(rpc, network, contracts, tx, &(&payer).into()).await {
+		Ok(v) => v,
+		Err(e) if !dry_run => {
+			eprintln!("Warning: pre-flight check skipped: {e}");
+			return Ok(true);
+		}
+		Err(e) => return Err(e.context("pre-flight check failed")),
+	};
+
+	match verdict {
+		Verdict::Accepted { cycles, fee } => {
+			println!("Pre-flight: accepted by the node ({cycles} cycles, fee {fee} shannons).")
+		}
+		Verdict::NeedsSignature => {
+			println!("Pre-flight: inputs, deps and capacity check out; contract scripts run once the wallet signs.")
+		}
+		Verdict::Rejected(reason) => anyhow::bail!("pre-flight check failed: {reason}"),
+	}
+	if dry_run {
+		println!("Dry run: nothing was signed or broadcast.");
+	}
+	Ok(!dry_run)
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use anyhow::Result;
+use ckb_jsonrpc_types::{Either, Status};
+
+use crate::cli::{Cli, ProofCommand};
+use crate::commands::{check_delegated_issuer, resolve_contracts, warn_retry};
+use crate::config::Config;
+use crate::crypto::{self, QrPayload, SignatureCheck};
+use crate::proof::ProofBundle;
+use crate::rpc::RpcClient;
+
+pub async fn run(cli: &Cli, cmd: &ProofCommand) -> Result<()> {
+	match cmd {
+		ProofCommand::Verify { file } => verify_bundle(cli, file).await,
+	}
+}
+
+/// Re-check a proof bundle from first principles: the signed message must
+/// match the QR payload, the signature must recover to the attendee, and
+/// the badge committed on-chain must commit to that exact signature.
+async fn verify_bundle(cli: &Cli, path: &Path) -> Result<()> {
+	let config = Config::load()?;
+	let bundle = ProofBundle::load(path)?;
+
+	// The bundle records its own network; only an explicit --rpc-url wins.
+	let rpc = match &cli.rpc_url {
+		Some(url) => RpcClient::new(url),
+		None => RpcClient::with_endpoints(config.rpc_urls(&bundle.network)),
+	}
+	.on_retry(warn_retry);
+	let contracts = resolve_contracts(&config, &bundle.network)?;
+
+	println!("Proof bundle: {}", path.display());
+	println!("  Attendee: {}", bundle.address);
+	println!("  Network:  {}", bundle.network);
+	println!();
+
+	// 1. The signed message must be exactly what the QR payload implies.
+	let qr = QrPayload::parse(&bundle.qr_data)
+		.ok_or_else(|| anyhow::anyhow!("bundle contains an invalid QR payload"))?;
+	let expected_msg = crypto::attendance_message(&qr.event_id, qr.timestamp, &bundle.address);
+	if bundle.message != expected_msg {
+		anyhow::bail!("signed message does not match the QR payload and attendee address");
+	}
+	println!("[ok] Message matches QR payload (event {}, ts {})", qr.event_id, qr.timestamp);
+	if let Some(issuer) = &qr.issuer {
+		if *issuer != bundle.issuer {
+			anyhow::bail!(
+				"bundle issuer {} is not the co-organizer {issuer} named in the QR",
+				bundle.issuer
+			);
+		}
+		let creator = check_delegated_issuer(
+			&config,
+			&rpc,
+			&bundle.network,
+			&qr.event_id,
+			issuer,
+			qr.timestamp,
+		)
+		.await
+		.map_err(|e| e.context(format!("co-organizer {issuer} is not delegated")))?;
+		println!("[ok] Issued through a window opened by co-organizer {issuer}, delegated by {creator}");
+	}
+	if let Some(qr_network) = &qr.network {
+		if *qr_network != bundle.network {
+			anyhow::bail!("QR payload is for {qr_network}, but the bundle is on {}", bundle.network);
+		}
+	}
+	// `signed_at` is written by the attendee's own CLI and is not signed,
+	// so it is only reported; the mint block bounds the timing below.
+	if let Some(signed_at) = bundle.signed_at {
+		println!(
+			"[--] Attendance signed {}s after the QR timestamp (self-reported, unverified)",
+			signed_at - qr.timestamp
+		);
+	}
+
+	// 2. Signature math.
+	let sig_check =
+		crypto::verify_message_signature(&bundle.message, &bundle.signature, &bundle.address)?;
+	match &sig_check {
+		SignatureCheck::Valid => println!("[ok] Signature recovers to the attendee's lock"),
+		SignatureCheck::Invalid => anyhow::bail!("signature does not match the attendee address"),
+		SignatureCheck::Unsupported(lock) => {
+			println!("[--] Signature not checked: unsupported lock ({lock})")
+		}
+	}
+
+	// 3. The mint transaction must be committed on-chain.
+	let info = rpc
+		.get_transaction(&bundle.badge_tx_hash)
+		.await?
+		.ok_or_else(|| anyhow::anyhow!("badge tx {} not found on-chain", bundle.badge_tx_hash))?;
+	if info.tx_status.status != Status::Committed {
+		anyhow::bail!(
+			"badge tx is not committed (status: {:?})",
+			info.tx_status.status
+		);
+	}
+	let block_hash = info
+		.tx_status
+		.block_hash
+		.map(|h| format!("{h:#x}"))
+		.ok_or_else(|| anyhow::anyhow!("node did not report a block hash for the badge tx"))?;
+	if let Some(recorded) = &bundle.block_hash {
+		if *recorded != block_hash {
+			anyhow::bail!("badge tx is committed in {block_hash}, not {recorded}");
+		}
+	}
+	println!("[ok] Badge tx committed in block {block_hash}");
+
+	// 4. One of its outputs must be the badge, committing to this signature.
+	let tx = match info.transaction.map(|t| t.inner) {
+		Some(Either::Left(view)) => view.inner,
+		_ => anyhow::bail!("node returned the badge tx in an unexpected format"),
+	};
+	let badge_code_hash = &contracts.dob_badge.code_hash;
+	let expected_args = crypto::build_type_script_args(&qr.event_id, &bundle.address);
+	let expected_data = crypto::build_badge_cell_data(
+		&qr.event_id,
+		&bundle.issuer,
+		Some(&crypto::proof_hash(&bundle.signature)),
+	);
+
+	let badge_found = tx.outputs.iter().zip(&tx.outputs_data).any(|(output, data)| {
+		output.type_.as_ref().is_some_and(|script| {
+			format!("{:#x}", script.code_hash) == *badge_code_hash
+				&& script.args.as_bytes() == expected_args.as_slice()
+		}) && data.as_bytes() == expected_data.as_slice()
+	});
+	if !badge_found {
+		anyhow::bail!("badge tx has no dob-badge output committing to this proof");
+	}
+	println!("[ok] Badge cell commits to this signature");
+
+	// 5. The QR code must predate the block the badge was minted in.
+	if let Some(header) = rpc.get_header(&block_hash).await? {
+		let block_ts = u64::from(header.inner.timestamp) as i64 / 1000;
+		if qr.timestamp > block_ts {
+			anyhow::bail!("QR timestamp {} is later than the mint block ({block_ts})", qr.timestamp);
+		}
+		println!("[ok] QR scanned {}s before the mint block", block_ts - qr.timestamp);
+	}
+
+	println!();
+	match sig_check {
+		SignatureCheck::Valid => println!("Proof VERIFIED."),
+		_ => println!("Proof verified on-chain; signature must be checked with the attendee's wallet."),
+	}
+
+	Ok(())
+}
@@ -2,13 +2,14 @@ use anyhow::Result;
 
 use crate::cli::{SignerArg, SignerCommand};
 use crate::config::{Config, SignerMethod};
+use crate::rpc::RpcClient;
 use crate::signer::browser;
 
 pub async fn run(cmd: &SignerCommand) -> Result<()> {
 	match cmd {
 		SignerCommand::Set { method } => set_method(method),
 		SignerCommand::Connect => connect().await,
-		SignerCommand::Status => show_status(),
+		SignerCommand::Status => show_status().await,
 	}
 }
 
@@ -52,7 +53,7 @@ async fn connect() -> Result<()> {
 	Ok(())
 }
 
-fn show_status() -> Result<()> {
+async fn show_status() -> Result<()> {
 	let config = Config::load()?;
 
 	let method = config
@@ -72,6 +73,14 @@ fn show_status() -> Result<()> {
 	println!("  Method:  {method}");
 	println!("  Address: {address}");
 	println!("  Network: {}", config.network.default);
-	println!("  RPC:     {}", config.rpc_url(&config.network.default));
+
+	let rpc = RpcClient::with_endpoints(config.rpc_urls(&config.network.default));
+	for (i, (url, health)) in rpc.health().await.into_iter().enumerate() {
+		let label = if i == 0 { "  RPC:    " } else { "          " };
+		match health {
+			Ok(tip) => println!("{label} {url} (tip {tip})"),
+			Err(e) => println!("{label} {url} (unreachable: {e})"),
+		}
+	}
 	Ok(())
 }
@@ -1,18 +1,26 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
 use anyhow::Result;
+use ckb_jsonrpc_types::{CellOutput, Either, JsonBytes, Script};
 
 use crate::cli::{Cli, TxCommand};
-use crate::commands::resolve_rpc;
+use crate::commands::{resolve_contracts, resolve_rpc};
 use crate::config::Config;
-use crate::rpc::RpcClient;
+use crate::contracts::NetworkContracts;
+use crate::crypto::BadgeCellData;
+use crate::rpc::{Committed, RpcClient, TxOutcome, TxProgress};
+
+/// Shannons per CKB.
+const SHANNONS_PER_CKB: u64 = 100_000_000;
 
 pub async fn run(cli: &Cli, cmd: &TxCommand) -> Result<()> {
 	let config = Config::load()?;
-	let rpc_url = resolve_rpc(cli, &config);
-	let rpc = RpcClient::new(&rpc_url);
+	let rpc = resolve_rpc(cli, &config);
 
 	match cmd {
 		TxCommand::Status { tx_hash } => {
-			let result = rpc.get_transaction(tx_hash)?;
+			let result = rpc.get_transaction(tx_hash).await?;
 			match result {
 				Some(info) => {
 					let status = info.tx_status.status;
@@ -26,5 +34,215 @@ pub async fn run(cli: &Cli, cmd: &TxCommand) -> Result<()> {
 			}
 			Ok(())
 		}
+		TxCommand::Show { tx_hash } => show_tx(cli, &config, &rpc, tx_hash).await,
+		TxCommand::Wait {
+			tx_hash,
+			confirmations,
+			timeout,
+		} => {
+			let timeout = Some(Duration::from_secs(*timeout));
+			match wait_for_tx(&rpc, tx_hash, *confirmations, timeout).await? {
+				Some(_) => Ok(()),
+				None => anyhow::bail!("timed out waiting for {tx_hash}"),
+			}
+		}
+	}
+}
+
+/// Wait for `tx_hash` to reach `confirmations` depth, printing progress as
+/// it changes.  Returns `None` on timeout and an error if the node
+/// rejects the transaction.
+pub async fn wait_for_tx(
+	rpc: &RpcClient,
+	tx_hash: &str,
+	confirmations: u64,
+	timeout: Option<Duration>,
+) -> Result<Option<Committed>> {
+	println!("Waiting for {tx_hash} ({confirmations} confirmation(s))...");
+	let outcome = rpc
+		.wait_for_tx(tx_hash, confirmations, timeout, |progress| {
+			let line = match progress {
+				TxProgress::Unknown => "not yet seen by the node".to_owned(),
+				TxProgress::Pending => "pending in the tx pool".to_owned(),
+				TxProgress::Proposed => "proposed".to_owned(),
+				TxProgress::Committed(c) => format!(
+					"committed in block {} ({}/{confirmations} confirmations)",
+					c.block_number, c.depth
+				),
+				TxProgress::Rejected(_) => "rejected".to_owned(),
+			};
+			println!("  status: {line}");
+		})
+		.await?;
+
+	match outcome {
+		TxOutcome::Confirmed(c) => {
+			println!("Confirmed in block {} ({:#x}).", c.block_number, c.block_hash);
+			Ok(Some(c))
+		}
+		TxOutcome::Rejected(reason) => anyhow::bail!("transaction rejected: {reason}"),
+		TxOutcome::TimedOut(_) => Ok(None),
 	}
 }
+
+/// Print a transaction with its inputs, outputs and fee, decoding any
+/// dob-badge or event-anchor outputs along the way.
+async fn show_tx(cli: &Cli, config: &Config, rpc: &RpcClient, tx_hash: &str) -> Result<()> {
+	let contracts = &resolve_contracts(config, cli.network.as_str())?;
+	let info = rpc
+		.get_transaction(tx_hash)
+		.await?
+		.ok_or_else(|| anyhow::anyhow!("transaction not found: {tx_hash}"))?;
+	let tx = match info.transaction.map(|t| t.inner) {
+		Some(Either::Left(view)) => view.inner,
+		_ => anyhow::bail!("node returned the transaction in an unexpected format"),
+	};
+
+	println!("Transaction: {tx_hash}");
+	print!("Status:      {:?}", info.tx_status.status);
+	match info.tx_status.block_hash {
+		Some(bh) => println!(" (block {bh:#x})"),
+		None => println!(),
+	}
+
+	// Input capacities live in the transactions that created them; fetch
+	// each parent once.  A missing parent just leaves the fee unknown.
+	let mut parents = HashMap::new();
+	let mut input_total = Some(0u64);
+	println!();
+	println!("Inputs ({}):", tx.inputs.len());
+	for (i, input) in tx.inputs.iter().enumerate() {
+		let prev = &input.previous_output;
+		let parent_hash = format!("{:#x}", prev.tx_hash);
+		if !parents.contains_key(&parent_hash) {
+			let parent = match rpc.get_transaction(&parent_hash).await? {
+				Some(p) => match p.transaction.map(|t| t.inner) {
+					Some(Either::Left(view)) => Some(view.inner.outputs),
+					_ => None,
+				},
+				None => None,
+			};
+			parents.insert(parent_hash.clone(), parent);
+		}
+		let index = u32::from(prev.index);
+		let spent = parents[&parent_hash]
+			.as_ref()
+			.and_then(|outputs| outputs.get(index as usize));
+
+		println!("  [{i}] {parent_hash}:{index}");
+		match spent {
+			Some(output) => {
+				let capacity = u64::from(output.capacity);
+				input_total = input_total.map(|t| t + capacity);
+				println!("      {}  {}", format_ckb(capacity), lock_label(cli, &output.lock));
+			}
+			None => {
+				input_total = None;
+				println!("      (previous output not available)");
+			}
+		}
+	}
+
+	let mut output_total = 0u64;
+	println!();
+	println!("Outputs ({}):", tx.outputs.len());
+	for (i, (output, data)) in tx.outputs.iter().zip(&tx.outputs_data).enumerate() {
+		let capacity = u64::from(output.capacity);
+		output_total += capacity;
+		println!("  [{i}] {}  {}", format_ckb(capacity), lock_label(cli, &output.lock));
+		describe_output(contracts, output, data);
+	}
+
+	println!();
+	println!("Total out:   {}", format_ckb(output_total));
+	match input_total {
+		Some(total) => {
+			println!("Total in:    {}", format_ckb(total));
+			match total.checked_sub(output_total) {
+				Some(fee) => println!("Fee:         {}", format_ckb(fee)),
+				None => println!("Fee:         (outputs exceed inputs)"),
+			}
+		}
+		None => println!("Fee:         unknown (some inputs could not be resolved)"),
+	}
+
+	Ok(())
+}
+
+/// Print the decoded type script and data of a PoP output, or a short
+/// summary for anything else.
+fn describe_output(contracts: &NetworkContracts, output: &CellOutput, data: &JsonBytes) {
+	let data = data.as_bytes();
+	let Some(type_script) = &output.type_ else {
+		if !data.is_empty() {
+			println!("      data: {} bytes", data.len());
+		}
+		return;
+	};
+
+	let code_hash = format!("{:#x}", type_script.code_hash);
+	let args = type_script.args.as_bytes();
+	if code_hash == contracts.dob_badge.code_hash {
+		println!("      type: dob-badge");
+		print_args(args, "address hash");
+		match BadgeCellData::parse(data) {
+			Some(badge) => {
+				let has_metadata = badge.flags & BadgeCellData::FLAG_HAS_METADATA != 0;
+				println!("      version:      {}", badge.version);
+				println!(
+					"      flags:        {:#04x}{}",
+					badge.flags,
+					if has_metadata { " (has_metadata)" } else { "" }
+				);
+				println!("      content hash: 0x{}", hex::encode(badge.content_hash));
+			}
+			None => println!("      data:         malformed ({} bytes, expected 34)", data.len()),
+		}
+	} else if code_hash == contracts.event_anchor.code_hash {
+		println!("      type: event-anchor");
+		print_args(args, "creator hash");
+		match serde_json::from_slice::<serde_json::Value>(data) {
+			Ok(json) => {
+				for line in serde_json::to_string_pretty(&json).unwrap_or_default().lines() {
+					println!("      {line}");
+				}
+			}
+			Err(_) => println!("      data:         not valid JSON ({} bytes)", data.len()),
+		}
+	} else {
+		println!("      type: {code_hash} (args 0x{})", hex::encode(args));
+		if !data.is_empty() {
+			println!("      data: {} bytes", data.len());
+		}
+	}
+}
+
+/// Print 40-byte PoP type args as their two 20-byte hash halves.
+fn print_args(args: &[u8], second: &str) {
+	if args.len() == 40 {
+		println!("      event hash:   0x{}", hex::encode(&args[..20]));
+		println!("      {second:<13} 0x{}", hex::encode(&args[20..]));
+	} else {
+		println!("      args:         0x{} (expected 40 bytes)", hex::encode(args));
+	}
+}
+
+/// Render a lock script as an address on the current network.
+fn lock_label(cli: &Cli, lock: &Script) -> String {
+	let network = cli.network.address_type();
+	let script = ckb_types::packed::Script::from(lock.clone());
+	let payload = ckb_sdk::AddressPayload::from(script);
+	ckb_sdk::Address::new(network, payload, true).to_string()
+}
+
+/// Format shannons as a CKB amount with up to eight decimals.
+fn format_ckb(shannons: u64) -> String {
+	let whole = shannons / SHANNONS_PER_CKB;
+	let frac = shannons % SHANNONS_PER_CKB;
+	if frac == 0 {
+		return format!("{whole} CKB");
+	}
+	let frac = format!("{frac:08}");
+	format!("{whole}.{} CKB", frac.trim_end_matches('0'))
+}
+
@@ -1,11 +1,20 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use crate::contracts::NetworkContracts;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
 	pub network: NetworkConfig,
 	pub signer: SignerConfig,
+	/// Contract deployments by network name, overriding the built-in ones.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub contracts: BTreeMap<String, NetworkContracts>,
+	/// Event registry endpoints by network name, overriding ckb-pop.xyz.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub backend: BTreeMap<String, BackendConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +22,47 @@ pub struct NetworkConfig {
 	pub default: String,
 	pub testnet_rpc: String,
 	pub mainnet_rpc: String,
+	/// Extra testnet endpoints, tried in order when the primary fails.
+	#[serde(default)]
+	pub testnet_fallback_rpcs: Vec<String>,
+	/// Extra mainnet endpoints, tried in order when the primary fails.
+	#[serde(default)]
+	pub mainnet_fallback_rpcs: Vec<String>,
+	#[serde(default = "default_devnet_rpc")]
+	pub devnet_rpc: String,
+	/// Extra devnet endpoints, tried in order when the primary fails.
+	#[serde(default)]
+	pub devnet_fallback_rpcs: Vec<String>,
+}
+
+fn default_devnet_rpc() -> String {
+	"http://127.0.0.1:8114".into()
+}
+
+/// Where the event registry API and the public event pages live.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendConfig {
+	#[serde(default = "default_api_url")]
+	pub api_url: String,
+	#[serde(default = "default_frontend_url")]
+	pub frontend_url: String,
+}
+
+fn default_api_url() -> String {
+	"https://ckb-pop-backend.fly.dev/api".into()
+}
+
+fn default_frontend_url() -> String {
+	"https://ckb-pop.xyz".into()
+}
+
+impl Default for BackendConfig {
+	fn default() -> Self {
+		Self {
+			api_url: default_api_url(),
+			frontend_url: default_frontend_url(),
+		}
+	}
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,11 +87,19 @@ impl Default for Config {
 				default: "testnet".into(),
 				testnet_rpc: "https://testnet.ckb.dev/rpc".into(),
 				mainnet_rpc: "https://mainnet.ckb.dev/rpc".into(),
+				// No fallbacks by default: failover only reaches nodes the
+				// user has configured.
+				testnet_fallback_rpcs: Vec::new(),
+				mainnet_fallback_rpcs: Vec::new(),
+				devnet_rpc: default_devnet_rpc(),
+				devnet_fallback_rpcs: Vec::new(),
 			},
 			signer: SignerConfig {
 				method: None,
 				address: None,
 			},
+			contracts: BTreeMap::new(),
+			backend: BTreeMap::new(),
 		}
 	}
 }
@@ -84,9 +142,43 @@ impl Config {
 	pub fn rpc_url(&self, network: &str) -> &str {
 		match network {
 			"mainnet" => &self.network.mainnet_rpc,
+			"devnet" => &self.network.devnet_rpc,
 			_ => &self.network.testnet_rpc,
 		}
 	}
+
+	/// Return every RPC endpoint for the network: the primary first, then
+	/// the fallbacks in the order they should be tried.
+	pub fn rpc_urls(&self, network: &str) -> Vec<String> {
+		let fallbacks = match network {
+			"mainnet" => &self.network.mainnet_fallback_rpcs,
+			"devnet" => &self.network.devnet_fallback_rpcs,
+			_ => &self.network.testnet_fallback_rpcs,
+		};
+		let mut urls = vec![self.rpc_url(network).to_owned()];
+		for url in fallbacks {
+			if !urls.contains(url) {
+				urls.push(url.clone());
+			}
+		}
+		urls
+	}
+
+	/// Event registry endpoints for the network: the `[backend.<network>]`
+	/// table if present, otherwise the public ckb-pop.xyz service for
+	/// testnet and mainnet.  Other networks must name their own registry,
+	/// so their events never reach the public one.
+	pub fn backend(&self, network: &str) -> anyhow::Result<BackendConfig> {
+		match self.backend.get(network) {
+			Some(backend) => Ok(backend.clone()),
+			None if matches!(network, "testnet" | "mainnet") => Ok(BackendConfig::default()),
+			None => anyhow::bail!(
+				"no event registry is configured for {network}. Add a [backend.{network}] table \
+				 to {}, or use --no-backend where the command offers it",
+				Self::path().display()
+			),
+		}
+	}
 }
 
 #[cfg(test)]
@@ -121,7 +213,70 @@ mod tests {
 		let c = Config::default();
 		assert_eq!(c.rpc_url("testnet"), "https://testnet.ckb.dev/rpc");
 		assert_eq!(c.rpc_url("mainnet"), "https://mainnet.ckb.dev/rpc");
+		assert_eq!(c.rpc_url("devnet"), "http://127.0.0.1:8114");
 		// Unknown network falls back to testnet.
-		assert_eq!(c.rpc_url("devnet"), "https://testnet.ckb.dev/rpc");
+		assert_eq!(c.rpc_url("staging"), "https://testnet.ckb.dev/rpc");
+		// Only the primary endpoint is used unless fallbacks are configured.
+		assert_eq!(c.rpc_urls("mainnet"), vec!["https://mainnet.ckb.dev/rpc"]);
+	}
+
+	#[test]
+	fn rpc_urls_put_primary_first() {
+		let mut c = Config::default();
+		c.network.testnet_fallback_rpcs = vec![
+			"https://testnet.ckb.dev/rpc".into(),
+			"http://127.0.0.1:8114".into(),
+		];
+		assert_eq!(
+			c.rpc_urls("testnet"),
+			vec!["https://testnet.ckb.dev/rpc", "http://127.0.0.1:8114"]
+		);
+	}
+
+	#[test]
+	fn configs_without_fallbacks_still_load() {
+		let old = r#"
+			[network]
+			default = "testnet"
+			testnet_rpc = "https://testnet.ckb.dev/rpc"
+			mainnet_rpc = "https://mainnet.ckb.dev/rpc"
+
+			[signer]
+		"#;
+		let c: Config = toml::from_str(old).unwrap();
+		assert_eq!(c.rpc_urls("mainnet"), vec!["https://mainnet.ckb.dev/rpc"]);
+		assert_eq!(c.rpc_url("devnet"), "http://127.0.0.1:8114");
+		assert!(c.contracts.is_empty());
+		assert_eq!(c.backend("testnet").unwrap(), BackendConfig::default());
+	}
+
+	#[test]
+	fn backend_is_configurable_per_network() {
+		let doc = r#"
+			[network]
+			default = "devnet"
+			testnet_rpc = "https://testnet.ckb.dev/rpc"
+			mainnet_rpc = "https://mainnet.ckb.dev/rpc"
+
+			[signer]
+
+			[backend.devnet]
+			api_url = "http://127.0.0.1:3000/api"
+		"#;
+		let c: Config = toml::from_str(doc).unwrap();
+		let devnet = c.backend("devnet").unwrap();
+		assert_eq!(devnet.api_url, "http://127.0.0.1:3000/api");
+		assert_eq!(devnet.frontend_url, "https://ckb-pop.xyz");
+		assert_eq!(c.backend("testnet").unwrap(), BackendConfig::default());
+	}
+
+	#[test]
+	fn only_public_networks_default_to_the_public_registry() {
+		let c = Config::default();
+		assert_eq!(c.backend("mainnet").unwrap(), BackendConfig::default());
+		for network in ["devnet", "staging"] {
+			let err = c.backend(network).unwrap_err().to_string();
+			assert!(err.contains(&format!("[backend.{network}]")), "{err}");
+		}
 	}
 }
@@ -1,58 +1,415 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use ckb_jsonrpc_types as json;
+use ckb_types::{packed, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::rpc::RpcClient;
+
+/// How a contract's cell dep points at its binary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepType {
+	/// The out point is the script binary itself.
+	#[default]
+	Code,
+	/// The out point is a dep group listing the binary and its deps.
+	DepGroup,
+}
+
 /// Metadata for a deployed on-chain script.
-#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContractInfo {
 	/// Type-ID code hash (0x-prefixed, 66 chars).
-	pub code_hash: &'static str,
+	pub code_hash: String,
 	/// Transaction hash where the script binary was deployed.
-	pub deploy_tx_hash: &'static str,
+	pub deploy_tx_hash: String,
 	/// Output index within the deploy transaction.
 	pub deploy_out_index: u32,
 	/// Data hash of the compiled script binary.
-	pub data_hash: &'static str,
+	pub data_hash: String,
+	/// Cell dep type for the deploy out point.
+	#[serde(default)]
+	pub dep_type: DepType,
 }
 
 /// The two PoP protocol scripts for a given network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NetworkContracts {
 	pub dob_badge: ContractInfo,
 	pub event_anchor: ContractInfo,
 }
 
-/// All known contract deployments, keyed by network.
+/// All known contract deployments, keyed by network name.
+///
+/// The built-in registry knows the public testnet deployment.  Devnets,
+/// staging deployments and (eventually) mainnet are added or overridden
+/// with `[contracts.<network>]` tables in `config.toml`, or top-level
+/// `[<network>]` tables in `contracts.toml`, which takes precedence.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Contracts {
-	testnet: NetworkContracts,
+	networks: BTreeMap<String, NetworkContracts>,
 }
 
 impl Contracts {
-	pub fn for_network(&self, network: &str) -> &NetworkContracts {
-		match network {
-			"mainnet" => {
-				eprintln!("Error: Mainnet contracts are not deployed yet. Use --network testnet.");
-				std::process::exit(1);
+	/// Path to the optional contract registry file.
+	pub fn path() -> PathBuf {
+		Config::dir().join("contracts.toml")
+	}
+
+	/// Deployments shipped with the CLI.
+	pub fn builtin() -> Self {
+		let testnet = NetworkContracts {
+			dob_badge: ContractInfo {
+				code_hash: "0xb36ed7616c4c87c0779a6c1238e78a84ea68a2638173f25ed140650e0454fbb9"
+					.into(),
+				deploy_tx_hash:
+					"0x9ae36ae06c449d704bc20af5c455c32a220f73249b5b95a15e8a1e352848fda9".into(),
+				deploy_out_index: 0,
+				data_hash: "0x3da692e19366c26dace65eaa1d6517ca9e4f555cb78a608bfb41d0ea4c5c468b"
+					.into(),
+				dep_type: DepType::Code,
+			},
+			event_anchor: ContractInfo {
+				code_hash: "0xd565d738ad5ac99addddc59fd3af5e0d54469dc9834cf766260c7e0d23c70b37"
+					.into(),
+				deploy_tx_hash:
+					"0x9ae36ae06c449d704bc20af5c455c32a220f73249b5b95a15e8a1e352848fda9".into(),
+				deploy_out_index: 1,
+				data_hash: "0xde6f3d1814ec3bf5aceaf8fe754f9c82affc4de9f277aa6519b5ad52e892807b"
+					.into(),
+				dep_type: DepType::Code,
+			},
+		};
+		Self {
+			networks: BTreeMap::from([("testnet".to_owned(), testnet)]),
+		}
+	}
+
+	/// Built-in deployments overlaid with those from `config.toml` and
+	/// then `contracts.toml`.
+	pub fn load(config: &Config) -> Result<Self> {
+		let mut registry = Self::builtin();
+		registry.extend(config.contracts.clone())?;
+
+		let path = Self::path();
+		if path.exists() {
+			let content = std::fs::read_to_string(&path)?;
+			registry
+				.merge_toml(&content)
+				.with_context(|| format!("invalid contract registry {}", path.display()))?;
+		}
+		Ok(registry)
+	}
+
+	/// Deployments for `network`, or an error naming where to define them.
+	pub fn for_network(&self, network: &str) -> Result<&NetworkContracts> {
+		self.networks.get(network).ok_or_else(|| {
+			anyhow::anyhow!(
+				"no PoP contracts are known for {network}. Define them under [{network}] in {}",
+				Self::path().display()
+			)
+		})
+	}
+
+	/// Parse a `contracts.toml` document and add its networks.
+	fn merge_toml(&mut self, content: &str) -> Result<()> {
+		let networks: BTreeMap<String, NetworkContracts> = toml::from_str(content)?;
+		self.extend(networks)
+	}
+
+	fn extend(&mut self, networks: BTreeMap<String, NetworkContracts>) -> Result<()> {
+		for (name, mut contracts) in networks {
+			for (label, info) in [
+				("dob_badge", &mut contracts.dob_badge),
+				("event_anchor", &mut contracts.event_anchor),
+			] {
+				info.normalize()
+					.with_context(|| format!("{name}.{label}"))?;
 			}
-			_ => &self.testnet,
+			self.networks.insert(name, contracts);
 		}
+		Ok(())
 	}
 }
 
-/// Global registry of deployed contract addresses.
-pub static CONTRACTS: Contracts = Contracts {
-	testnet: NetworkContracts {
-		dob_badge: ContractInfo {
-			code_hash: "0xb36ed7616c4c87c0779a6c1238e78a84ea68a2638173f25ed140650e0454fbb9",
-			deploy_tx_hash:
-				"0x9ae36ae06c449d704bc20af5c455c32a220f73249b5b95a15e8a1e352848fda9",
-			deploy_out_index: 0,
-			data_hash: "0x3da692e19366c26dace65eaa1d6517ca9e4f555cb78a608bfb41d0ea4c5c468b",
-		},
-		event_anchor: ContractInfo {
-			code_hash: "0xd565d738ad5ac99addddc59fd3af5e0d54469dc9834cf766260c7e0d23c70b37",
-			deploy_tx_hash:
-				"0x9ae36ae06c449d704bc20af5c455c32a220f73249b5b95a15e8a1e352848fda9",
-			deploy_out_index: 1,
-			data_hash: "0xde6f3d1814ec3bf5aceaf8fe754f9c82affc4de9f277aa6519b5ad52e892807b",
+impl ContractInfo {
+	/// The configured deploy out point.
+	pub fn out_point(&self) -> Result<json::OutPoint> {
+		Ok(json::OutPoint {
+			tx_hash: self
+				.deploy_tx_hash
+				.trim_start_matches("0x")
+				.parse()
+				.map_err(|e| anyhow::anyhow!("invalid deploy_tx_hash: {e}"))?,
+			index: self.deploy_out_index.into(),
+		})
+	}
+
+	/// A copy of this deployment with its cell dep at another out point.
+	pub fn at(&self, tx_hash: &str, index: u32) -> Self {
+		Self {
+			deploy_tx_hash: tx_hash.to_owned(),
+			deploy_out_index: index,
+			..self.clone()
+		}
+	}
+
+	/// Check every hash is 32 bytes of hex, and store them lowercase and
+	/// 0x-prefixed so they compare equal to hashes formatted from RPC data.
+	fn normalize(&mut self) -> Result<()> {
+		for (field, value) in [
+			("code_hash", &mut self.code_hash),
+			("deploy_tx_hash", &mut self.deploy_tx_hash),
+			("data_hash", &mut self.data_hash),
+		] {
+			let hex = value.strip_prefix("0x").unwrap_or(value).to_ascii_lowercase();
+			if hex.len() != 64 || hex::decode(&hex).is_err() {
+				anyhow::bail!("{field} must be a 32-byte hex string, got {value:?}");
+			}
+			*value = format!("0x{hex}");
+		}
+		Ok(())
+	}
+}
+
+/// What the chain holds at a contract's configured deploy out point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeploymentReport {
+	/// Whether the deploy out point is an unspent cell.
+	pub live: bool,
+	/// Out point of the cell holding the binary; differs from the deploy
+	/// out point when that is a dep group.
+	pub binary_out_point: Option<String>,
+	/// blake2b hash of the binary cell's data.
+	pub data_hash: Option<String>,
+	/// Args of the binary cell's Type-ID type script (0x-prefixed).
+	pub type_id_args: Option<String>,
+}
+
+impl DeploymentReport {
+	/// Every way the chain disagrees with `info`; empty when it matches.
+	/// The contract is identified by its Type-ID, whose args are
+	/// `expected_args`; new code under the same Type-ID is an upgrade
+	/// (see [`Self::upgrade`]), not a problem.
+	pub fn problems(&self, info: &ContractInfo, expected_args: &str) -> Vec<String> {
+		if !self.live {
+			return vec![format!(
+				"deploy cell {}:{} is not live (spent or never created)",
+				info.deploy_tx_hash, info.deploy_out_index
+			)];
+		}
+		if self.data_hash.is_none() {
+			return vec!["no cell in the deployment carries the script binary".into()];
+		}
+		match &self.type_id_args {
+			Some(args) if args == expected_args => Vec::new(),
+			Some(args) => vec![format!("Type-ID args are {args}, expected {expected_args}")],
+			None => vec!["binary cell has no Type-ID type script".into()],
+		}
+	}
+
+	/// A note when the binary differs from the `data_hash` the registry
+	/// pins, i.e. the contract was upgraded under its Type-ID.
+	pub fn upgrade(&self, info: &ContractInfo) -> Option<String> {
+		self.data_hash
+			.as_ref()
+			.filter(|h| **h != info.data_hash)
+			.map(|h| format!("code upgraded: data hash is {h}, registry pins {}", info.data_hash))
+	}
+}
+
+/// Fetch a contract's deploy cell and hash what is actually on-chain.
+/// For a dep group, the member whose type script hashes to the expected
+/// code hash is treated as the binary (the first member if none does).
+pub async fn inspect(rpc: &RpcClient, info: &ContractInfo) -> Result<DeploymentReport> {
+	let deploy = rpc.get_live_cell(info.out_point()?).await?;
+	let Some(cell) = deploy.cell.filter(|_| deploy.status == "live") else {
+		return Ok(DeploymentReport {
+			live: false,
+			binary_out_point: None,
+			data_hash: None,
+			type_id_args: None,
+		});
+	};
+
+	let (binary, binary_out_point) = match info.dep_type {
+		DepType::Code => {
+			let label = format!("{}:{}", info.deploy_tx_hash, info.deploy_out_index);
+			(Some(cell), Some(label))
+		}
+		DepType::DepGroup => {
+			let data = cell.data.map(|d| d.content.into_bytes()).unwrap_or_default();
+			let members = packed::OutPointVec::from_slice(&data)
+				.map_err(|e| anyhow::anyhow!("dep group data is not an out point list: {e}"))?;
+			let mut chosen = None;
+			for member in members.into_iter() {
+				let out_point = json::OutPoint::from(member);
+				let label = format!("{:#x}:{}", out_point.tx_hash, u32::from(out_point.index));
+				let Some(member_cell) = rpc.get_live_cell(out_point).await?.cell else {
+					continue;
+				};
+				let matches = type_hash(&member_cell.output).as_deref() == Some(&info.code_hash);
+				if matches || chosen.is_none() {
+					chosen = Some((member_cell, label));
+				}
+				if matches {
+					break;
+				}
+			}
+			match chosen {
+				Some((cell, label)) => (Some(cell), Some(label)),
+				None => (None, None),
+			}
+		}
+	};
+
+	Ok(DeploymentReport {
+		live: true,
+		binary_out_point,
+		data_hash: binary.as_ref().and_then(|c| c.data.as_ref()).map(|d| {
+			format!("0x{}", hex::encode(ckb_hash::blake2b_256(d.content.as_bytes())))
+		}),
+		type_id_args: binary
+			.as_ref()
+			.and_then(|c| c.output.type_.as_ref())
+			.filter(|s| s.code_hash == ckb_sdk::constants::TYPE_ID_CODE_HASH)
+			.map(|s| format!("0x{}", hex::encode(s.args.as_bytes()))),
+	})
+}
+
+// -- Type-ID resolution --
+
+/// How long a resolved code cell is trusted before the indexer is asked
+/// again.  Upgrades are rare; this only bounds how stale a dep can get.
+const CELL_DEP_TTL_SECS: i64 = 600;
+
+/// Where a contract's code cell was last found, cached between runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedCellDep {
+	/// Type-ID args, which never change across upgrades.
+	type_id_args: String,
+	tx_hash: String,
+	index: u32,
+	/// Unix time of the lookup.
+	resolved_at: i64,
+}
+
+/// Resolved code cells keyed by `<network>:<code hash>`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CellDepCache {
+	#[serde(default)]
+	entries: BTreeMap<String, CachedCellDep>,
+}
+
+impl CellDepCache {
+	fn path() -> PathBuf {
+		Config::dir().join("cache").join("cell_deps.json")
+	}
+
+	/// Load the cache; a missing or unreadable file is just an empty cache.
+	fn load() -> Self {
+		std::fs::read_to_string(Self::path())
+			.ok()
+			.and_then(|s| serde_json::from_str(&s).ok())
+			.unwrap_or_default()
+	}
+
+	fn save(&self) -> Result<()> {
+		let path = Self::path();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+}
+
+/// Point `info` at the live cell currently carrying its Type-ID, so that
+/// an upgraded contract is used without editing the registry.
+///
+/// Dep groups are returned unchanged: the group cell has no Type-ID of its
+/// own to follow.
+pub async fn resolve_live(
+	rpc: &RpcClient,
+	network: &str,
+	info: &ContractInfo,
+) -> Result<ContractInfo> {
+	if info.dep_type == DepType::DepGroup {
+		return Ok(info.clone());
+	}
+
+	let key = format!("{network}:{}", info.code_hash);
+	let mut cache = CellDepCache::load();
+	let now = chrono::Utc::now().timestamp();
+	let cached = cache.entries.get(&key).cloned();
+	if let Some(hit) = cached.as_ref().filter(|c| now - c.resolved_at < CELL_DEP_TTL_SECS) {
+		return Ok(info.at(&hit.tx_hash, hit.index));
+	}
+
+	let args = match &cached {
+		Some(c) => hex::decode(c.type_id_args.trim_start_matches("0x"))?,
+		None => type_id_args(rpc, info).await?,
+	};
+	let code_hash = format!("{:#x}", ckb_sdk::constants::TYPE_ID_CODE_HASH);
+	let cell = rpc
+		.find_exact_type_cell(&code_hash, args.clone())
+		.await?
+		.ok_or_else(|| anyhow::anyhow!("no live cell carries the Type-ID {}", info.code_hash))?;
+	let index = u32::from(cell.out_point.index);
+
+	cache.entries.insert(
+		key,
+		CachedCellDep {
+			type_id_args: format!("0x{}", hex::encode(&args)),
+			tx_hash: cell.tx_hash(),
+			index,
+			resolved_at: now,
 		},
-	},
-};
+	);
+	// A cache that cannot be written only costs a lookup next time.
+	let _ = cache.save();
+	Ok(info.at(&cell.tx_hash(), index))
+}
+
+/// Read the Type-ID args from the configured deploy transaction.  The
+/// transaction stays retrievable after its output is spent by an upgrade.
+pub async fn type_id_args(rpc: &RpcClient, info: &ContractInfo) -> Result<Vec<u8>> {
+	let tx = rpc
+		.get_transaction(&info.deploy_tx_hash)
+		.await?
+		.and_then(|t| t.transaction)
+		.ok_or_else(|| anyhow::anyhow!("deploy transaction {} not found", info.deploy_tx_hash))?;
+	let tx = match tx.inner {
+		json::Either::Left(view) => view.inner,
+		json::Either::Right(_) => {
+			anyhow::bail!("node returned the transaction in an unexpected format")
+		}
+	};
+	let output = tx
+		.outputs
+		.get(info.deploy_out_index as usize)
+		.ok_or_else(|| anyhow::anyhow!("deploy transaction has no output {}", info.deploy_out_index))?;
+	let script = output
+		.type_
+		.as_ref()
+		.filter(|s| s.code_hash == ckb_sdk::constants::TYPE_ID_CODE_HASH)
+		.ok_or_else(|| anyhow::anyhow!("deploy cell has no Type-ID type script"))?;
+	if type_hash(output).as_deref() != Some(&info.code_hash) {
+		anyhow::bail!("deploy cell's Type-ID does not hash to {}", info.code_hash);
+	}
+	Ok(script.args.as_bytes().to_vec())
+}
+
+fn type_hash(output: &json::CellOutput) -> Option<String> {
+	output.type_.clone().map(|script| {
+		let script: packed::Script = script.into();
+		format!("0x{}", hex::encode(script.calc_script_hash().raw_data()))
+	})
+}
 
 #[cfg(test)]
 mod tests {
@@ -60,7 +417,8 @@ mod tests {
 
 	#[test]
 	fn testnet_code_hashes_are_valid_hex() {
-		let c = CONTRACTS.for_network("testnet");
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
 		for info in [&c.dob_badge, &c.event_anchor] {
 			let hex = info.code_hash.strip_prefix("0x").unwrap();
 			assert_eq!(hex.len(), 64, "code_hash should be 32 bytes");
@@ -70,9 +428,97 @@ mod tests {
 
 	#[test]
 	fn both_contracts_share_deploy_tx() {
-		let c = CONTRACTS.for_network("testnet");
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
 		assert_eq!(c.dob_badge.deploy_tx_hash, c.event_anchor.deploy_tx_hash);
 		assert_eq!(c.dob_badge.deploy_out_index, 0);
 		assert_eq!(c.event_anchor.deploy_out_index, 1);
 	}
+
+	#[test]
+	fn unknown_network_is_an_error() {
+		assert!(Contracts::builtin().for_network("mainnet").is_err());
+	}
+
+	#[test]
+	fn registry_file_adds_and_overrides_networks() {
+		let hash = |b: &str| format!("0x{}", b.repeat(64));
+		let doc = format!(
+			r#"
+			[devnet.dob_badge]
+			code_hash = "{}"
+			deploy_tx_hash = "{}"
+			deploy_out_index = 0
+			data_hash = "{}"
+			dep_type = "dep_group"
+
+			[devnet.event_anchor]
+			code_hash = "{}"
+			deploy_tx_hash = "{}"
+			deploy_out_index = 1
+			data_hash = "{}"
+			"#,
+			hash("A"),
+			hash("b"),
+			hash("c"),
+			hash("d"),
+			hash("b"),
+			hash("e"),
+		);
+
+		let mut registry = Contracts::builtin();
+		registry.merge_toml(&doc).unwrap();
+		let devnet = registry.for_network("devnet").unwrap();
+		assert_eq!(devnet.dob_badge.code_hash, hash("a"));
+		assert_eq!(devnet.dob_badge.dep_type, DepType::DepGroup);
+		assert_eq!(devnet.event_anchor.dep_type, DepType::Code);
+		assert!(registry.for_network("testnet").is_ok());
+
+		let bad = doc.replace(&hash("c"), "0x1234");
+		assert!(Contracts::builtin().merge_toml(&bad).is_err());
+	}
+
+	#[test]
+	fn moved_deployment_keeps_everything_but_the_out_point() {
+		let registry = Contracts::builtin();
+		let info = &registry.for_network("testnet").unwrap().dob_badge;
+		let moved = info.at("0xabc", 3);
+		assert_eq!(moved.deploy_tx_hash, "0xabc");
+		assert_eq!(moved.deploy_out_index, 3);
+		assert_eq!(moved.code_hash, info.code_hash);
+		assert_eq!(moved.data_hash, info.data_hash);
+	}
+
+	#[test]
+	fn report_checks_the_type_id_and_notes_upgrades() {
+		let registry = Contracts::builtin();
+		let info = &registry.for_network("testnet").unwrap().dob_badge;
+		let args = format!("0x{}", "11".repeat(32));
+		let matching = DeploymentReport {
+			live: true,
+			binary_out_point: None,
+			data_hash: Some(info.data_hash.clone()),
+			type_id_args: Some(args.clone()),
+		};
+		assert!(matching.problems(info, &args).is_empty());
+		assert_eq!(matching.upgrade(info), None);
+
+		let upgraded = DeploymentReport {
+			data_hash: Some(format!("0x{}", "0".repeat(64))),
+			..matching.clone()
+		};
+		assert!(upgraded.problems(info, &args).is_empty());
+		assert!(upgraded.upgrade(info).unwrap().contains("code upgraded"));
+
+		let impostor = DeploymentReport {
+			type_id_args: Some(format!("0x{}", "22".repeat(32))),
+			..matching.clone()
+		};
+		assert_eq!(impostor.problems(info, &args).len(), 1);
+		let untyped = DeploymentReport { type_id_args: None, ..matching.clone() };
+		assert_eq!(untyped.problems(info, &args).len(), 1);
+
+		let spent = DeploymentReport { live: false, ..matching };
+		assert_eq!(spent.problems(info, &args).len(), 1);
+	}
 }
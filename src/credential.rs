@@ -0,0 +1,315 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::crypto::{self, SignatureCheck};
+
+/// Document formats a badge can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+	/// 1EdTech Open Badges 3.0 (`OpenBadgeCredential`).
+	OpenBadges,
+	/// Plain W3C Verifiable Credential (data model 2.0).
+	Vc,
+}
+
+/// Everything the chain tells us about one badge, gathered by the caller
+/// before a credential is built.
+#[derive(Debug, Clone)]
+pub struct BadgeEvidence {
+	pub network: String,
+	pub event_id: String,
+	/// Address holding the badge.
+	pub holder: String,
+	/// Event creator, taken from the event-anchor cell.
+	pub issuer: String,
+	/// Issuer recorded in the badge content: the creator or organizer who
+	/// minted it, or the attendee for badges minted by `attend`.
+	pub badge_issuer: String,
+	/// Attendance proof hash in the badge content, if it has one.
+	pub proof_hash: Option<String>,
+	pub tx_hash: String,
+	pub index: u32,
+	/// Badge type-script args, 0x-prefixed hex.
+	pub type_args: String,
+	/// Badge cell data, 0x-prefixed hex.
+	pub cell_data: String,
+	pub anchor_tx_hash: Option<String>,
+	/// Event-anchor type-script args, 0x-prefixed hex.  They commit to the
+	/// event and its creator.
+	pub anchor_type_args: String,
+	pub metadata_hash: Option<String>,
+	/// RFC 3339 timestamp of the block the badge was minted in.
+	pub minted_at: String,
+}
+
+/// Build an unsigned credential document for a badge.
+pub fn build(format: Format, ev: &BadgeEvidence) -> Value {
+	let id = format!("urn:ckb-pop:badge:{}:{}:{}", ev.network, ev.tx_hash, ev.index);
+	let evidence = json!([{
+		"type": ["Evidence", "CkbCellEvidence"],
+		"network": ev.network,
+		"outPoint": { "txHash": ev.tx_hash, "index": ev.index },
+		"typeArgs": ev.type_args,
+		"cellData": ev.cell_data,
+		"badgeIssuer": ev.badge_issuer,
+		"proofHash": ev.proof_hash,
+		"eventAnchor": {
+			"txHash": ev.anchor_tx_hash,
+			"typeArgs": ev.anchor_type_args,
+			"metadataHash": ev.metadata_hash,
+		},
+	}]);
+
+	match format {
+		Format::OpenBadges => json!({
+			"@context": [
+				"https://www.w3.org/ns/credentials/v2",
+				"https://purl.imsglobal.org/spec/ob/v3p0/context-3.0.3.json",
+			],
+			"id": id,
+			"type": ["VerifiableCredential", "OpenBadgeCredential"],
+			"name": format!("CKB PoP attendance: {}", ev.event_id),
+			"issuer": {
+				"id": address_uri(&ev.issuer),
+				"type": ["Profile"],
+				"name": ev.issuer,
+			},
+			"validFrom": ev.minted_at,
+			"credentialSubject": {
+				"id": address_uri(&ev.holder),
+				"type": ["AchievementSubject"],
+				"achievement": {
+					"id": format!("urn:ckb-pop:event:{}", ev.event_id),
+					"type": ["Achievement"],
+					"name": format!("Attended event {}", ev.event_id),
+					"criteria": {
+						"narrative": "Holder minted a soulbound dob-badge for this event on Nervos CKB.",
+					},
+				},
+			},
+			"evidence": evidence,
+		}),
+		Format::Vc => json!({
+			"@context": ["https://www.w3.org/ns/credentials/v2"],
+			"id": id,
+			"type": ["VerifiableCredential", "CkbPopAttendanceCredential"],
+			"issuer": address_uri(&ev.issuer),
+			"validFrom": ev.minted_at,
+			"credentialSubject": {
+				"id": address_uri(&ev.holder),
+				"attended": { "eventId": ev.event_id },
+			},
+			"evidence": evidence,
+		}),
+	}
+}
+
+/// The message a wallet signs to seal a credential: the
+/// [canonical hash](crypto::canonical_hash) of the document with any
+/// existing `proof` removed.
+pub fn signing_message(doc: &Value) -> Result<String> {
+	let digest = hex::encode(crypto::canonical_hash(&unsigned(doc))?);
+	Ok(format!("CKB-PoP-Credential|{digest}"))
+}
+
+fn unsigned(doc: &Value) -> Value {
+	let mut unsigned = doc.clone();
+	if let Some(obj) = unsigned.as_object_mut() {
+		obj.remove("proof");
+	}
+	unsigned
+}
+
+/// Attach a wallet signature produced over [`signing_message`].
+pub fn attach_proof(doc: &mut Value, signer: &str, signature: &str, created: &str) {
+	let purpose = if issuer_address(doc) == Some(signer) {
+		"assertionMethod"
+	} else {
+		"authentication"
+	};
+	doc["proof"] = json!({
+		"type": "CkbSecp256k1Signature",
+		"created": created,
+		"verificationMethod": address_uri(signer),
+		"proofPurpose": purpose,
+		"proofValue": signature,
+	});
+}
+
+/// Verify a credential without touching the network: the evidence must
+/// be self-consistent and the proof must be a valid signature by either
+/// the issuer or the holder.  Self-consistent means the badge cell data
+/// is recomputed from the event and badge content, and the anchor's type
+/// args commit to the credential's issuer as the event creator.
+pub fn verify(doc: &Value) -> Result<SignatureCheck> {
+	let proof = doc.get("proof").ok_or_else(|| anyhow!("credential has no proof"))?;
+	let signer = proof["verificationMethod"]
+		.as_str()
+		.and_then(|m| m.strip_prefix("urn:ckb:"))
+		.ok_or_else(|| anyhow!("proof has no CKB verification method"))?;
+	let signature = proof["proofValue"]
+		.as_str()
+		.ok_or_else(|| anyhow!("proof has no proofValue"))?;
+
+	let holder = doc
+		.pointer("/credentialSubject/id")
+		.and_then(Value::as_str)
+		.and_then(|id| id.strip_prefix("urn:ckb:"))
+		.ok_or_else(|| anyhow!("credential has no CKB subject"))?;
+	if Some(signer) != issuer_address(doc) && signer != holder {
+		anyhow::bail!("proof is signed by {signer}, who is neither issuer nor holder");
+	}
+
+	let evidence = doc
+		.pointer("/evidence/0")
+		.ok_or_else(|| anyhow!("credential has no evidence"))?;
+	let event_id = doc
+		.pointer("/credentialSubject/achievement/id")
+		.or_else(|| doc.pointer("/credentialSubject/attended/eventId"))
+		.and_then(Value::as_str)
+		.map(|id| id.strip_prefix("urn:ckb-pop:event:").unwrap_or(id))
+		.ok_or_else(|| anyhow!("credential does not name an event"))?;
+
+	let expected_args = format!(
+		"0x{}",
+		hex::encode(crypto::build_type_script_args(event_id, holder))
+	);
+	if evidence["typeArgs"].as_str() != Some(expected_args.as_str()) {
+		anyhow::bail!("evidence type args do not match the event and holder");
+	}
+	let cell_data = evidence["cellData"]
+		.as_str()
+		.and_then(|d| hex::decode(d.strip_prefix("0x").unwrap_or(d)).ok())
+		.ok_or_else(|| anyhow!("evidence cell data is not hex"))?;
+	let badge_issuer = evidence["badgeIssuer"]
+		.as_str()
+		.ok_or_else(|| anyhow!("evidence does not name the badge issuer"))?;
+	let proof_hash = evidence["proofHash"].as_str();
+	if crypto::build_badge_cell_data(event_id, badge_issuer, proof_hash) != cell_data {
+		anyhow::bail!("evidence cell data does not match the event and badge content");
+	}
+
+	let issuer = issuer_address(doc).ok_or_else(|| anyhow!("credential has no CKB issuer"))?;
+	let anchor_args = format!(
+		"0x{}",
+		hex::encode(crypto::build_type_script_args(event_id, issuer))
+	);
+	if evidence.pointer("/eventAnchor/typeArgs").and_then(Value::as_str) != Some(anchor_args.as_str()) {
+		anyhow::bail!("credential issuer {issuer} is not the creator in the event anchor");
+	}
+
+	crypto::verify_message_signature(&signing_message(doc)?, signature, signer)
+}
+
+fn address_uri(address: &str) -> String {
+	format!("urn:ckb:{address}")
+}
+
+fn issuer_address(doc: &Value) -> Option<&str> {
+	doc.get("issuer")
+		.and_then(|i| i.as_str().or_else(|| i.get("id").and_then(Value::as_str)))
+		.and_then(|id| id.strip_prefix("urn:ckb:"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn evidence(holder: &str, issuer: &str) -> BadgeEvidence {
+		let data = crypto::build_badge_cell_data("evt1", issuer, None);
+		BadgeEvidence {
+			network: "testnet".into(),
+			event_id: "evt1".into(),
+			holder: holder.into(),
+			issuer: issuer.into(),
+			badge_issuer: issuer.into(),
+			proof_hash: None,
+			tx_hash: "0x01".into(),
+			index: 0,
+			type_args: format!("0x{}", hex::encode(crypto::build_type_script_args("evt1", holder))),
+			cell_data: format!("0x{}", hex::encode(data)),
+			anchor_tx_hash: None,
+			anchor_type_args: format!("0x{}", hex::encode(crypto::build_type_script_args("evt1", issuer))),
+			metadata_hash: None,
+			minted_at: "2026-01-01T00:00:00Z".into(),
+		}
+	}
+
+	#[test]
+	fn signing_message_ignores_proof() {
+		let mut doc = build(Format::Vc, &evidence("ckt1qholder", "ckt1qissuer"));
+		let before = signing_message(&doc).unwrap();
+		attach_proof(&mut doc, "ckt1qholder", "0xabcd", "2026-01-01T00:00:00Z");
+		assert_eq!(signing_message(&doc).unwrap(), before);
+		assert_eq!(doc["proof"]["proofPurpose"], "authentication");
+	}
+
+	#[test]
+	fn signed_credential_verifies_in_both_formats() {
+		let (_, holder) = crypto::wallet_sign(3, "");
+		for format in [Format::OpenBadges, Format::Vc] {
+			let mut doc = build(format, &evidence(&holder, "ckt1qissuer"));
+			let (sig, _) = crypto::wallet_sign(3, &signing_message(&doc).unwrap());
+			attach_proof(&mut doc, &holder, &sig, "2026-01-01T00:00:00Z");
+			assert_eq!(verify(&doc).unwrap(), SignatureCheck::Valid);
+		}
+	}
+
+	#[test]
+	fn signing_message_golden_vector() {
+		let doc = json!({
+			"type": ["VerifiableCredential"],
+			"issuer": "urn:ckb:ckt1qissuer",
+			"evidence": [{ "index": 0, "txHash": "0x01" }],
+			"proof": { "proofValue": "0xabcd" },
+		});
+		// SHA256(0x01 || {"evidence":[{"index":0,"txHash":"0x01"}],"issuer":"urn:ckb:ckt1qissuer","type":["VerifiableCredential"]})
+		assert_eq!(signing_message(&doc).unwrap(), "CKB-PoP-Credential|8322a73da28d965ede88138c120556e19fbe5da059f19ff7c2dad30088b21207");
+	}
+
+	#[test]
+	fn tampered_credential_fails() {
+		let (_, holder) = crypto::wallet_sign(3, "");
+		let mut doc = build(Format::Vc, &evidence(&holder, "ckt1qissuer"));
+		let (sig, _) = crypto::wallet_sign(3, &signing_message(&doc).unwrap());
+		attach_proof(&mut doc, &holder, &sig, "2026-01-01T00:00:00Z");
+
+		doc["validFrom"] = json!("2020-01-01T00:00:00Z");
+		assert_eq!(verify(&doc).unwrap(), SignatureCheck::Invalid);
+
+		doc["credentialSubject"]["attended"]["eventId"] = json!("evt2");
+		assert!(verify(&doc).is_err());
+	}
+
+	#[test]
+	fn evidence_must_commit_to_the_badge_content_and_creator() {
+		let (_, holder) = crypto::wallet_sign(3, "");
+		let seal = |doc: &mut Value| {
+			let (sig, _) = crypto::wallet_sign(3, &signing_message(doc).unwrap());
+			attach_proof(doc, &holder, &sig, "2026-01-01T00:00:00Z");
+		};
+
+		// A badge minted by `attend` names the attendee and a proof hash.
+		let mut ev = evidence(&holder, "ckt1qissuer");
+		ev.badge_issuer = holder.clone();
+		ev.proof_hash = Some("ab".into());
+		let data = crypto::build_badge_cell_data("evt1", &holder, Some("ab"));
+		ev.cell_data = format!("0x{}", hex::encode(data));
+		let mut doc = build(Format::OpenBadges, &ev);
+		seal(&mut doc);
+		assert_eq!(verify(&doc).unwrap(), SignatureCheck::Valid);
+
+		// Any 34-byte cell data starting with 0x01 is not enough.
+		let mut forged = build(Format::Vc, &evidence(&holder, "ckt1qissuer"));
+		forged["evidence"][0]["cellData"] = json!(format!("0x0101{}", "00".repeat(32)));
+		seal(&mut forged);
+		assert!(verify(&forged).is_err());
+
+		// The issuer must be the creator the anchor commits to.
+		let mut ev = evidence(&holder, "ckt1qissuer");
+		ev.issuer = "ckt1qsomeoneelse".into();
+		let mut doc = build(Format::Vc, &ev);
+		seal(&mut doc);
+		assert!(verify(&doc).is_err());
+	}
+}
@@ -1,4 +1,7 @@
+use anyhow::{anyhow, Result};
+use ckb_types::{core::ScriptHashType, packed::Script, prelude::*, H256};
 use hmac::{Hmac, Mac};
+use serde_json::Value;
 use sha2::{Digest, Sha256};
 
 type HmacSha256 = Hmac<Sha256>;
@@ -10,8 +13,8 @@ type HmacSha256 = Hmac<Sha256>;
 ///
 /// Note: the canonical event ID for events registered on ckb-pop.xyz is
 /// assigned by the backend after signing `CKB-PoP-CreateEvent|{nonce}`.
-/// This function is retained for testing and offline use cases.
-#[allow(dead_code)]
+/// This function derives the ID for `event create --no-backend`, and the
+/// provisional anchors used in pre-flight checks.
 pub fn compute_event_id(creator_address: &str, timestamp_secs: i64, nonce: &str) -> String {
 	let mut h = Sha256::new();
 	h.update(creator_address.as_bytes());
@@ -20,6 +23,13 @@ pub fn compute_event_id(creator_address: &str, timestamp_secs: i64, nonce: &str)
 	hex::encode(h.finalize())
 }
 
+/// Whether `id` has the shape of an event ID: 64 hex characters.  IDs read
+/// from QR codes are untrusted and end up in file names, so anything else
+/// is refused.
+pub fn is_event_id(id: &str) -> bool {
+	id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
 // -- Type-script argument helpers --
 
 /// Build the 40-byte args used by event-anchor type scripts and badge
@@ -33,31 +43,237 @@ pub fn build_type_script_args(primary: &str, secondary: &str) -> Vec<u8> {
 
 // -- QR payload --
 
-/// The three-field payload encoded in every attendance QR code.
+/// Path of the attend page under the frontend URL.
+pub const QR_URL_PATH: &str = "/a";
+
+/// Seconds between QR code rotations.
+pub const QR_PERIOD: u32 = 30;
+
+/// Oldest QR code `attend` accepts, in seconds: two rotations.  Fixed, not
+/// taken from the code, so a code cannot grant itself a longer life.
+pub const QR_MAX_AGE: i64 = 2 * QR_PERIOD as i64;
+
+/// The payload encoded in every attendance QR code.
+///
+/// Version 1 is the original `event_id|timestamp|hmac`.  Version 2 adds
+/// optional fields that tie a code to its window and network.  Later
+/// versions keep the version 2 layout and may add fields; readers ignore
+/// fields they do not know.
 #[derive(Debug, Clone, PartialEq)]
 pub struct QrPayload {
+	/// Encoding version, [`QrPayload::VERSION`] for codes made now.
+	pub version: u32,
 	pub event_id: String,
 	pub timestamp: i64,
 	pub hmac: String,
+	/// Organizer who opened the window, when it is a delegate rather than
+	/// the event creator.  Becomes the badge issuer.
+	pub issuer: Option<String>,
+	/// Start of the window the code belongs to (Unix timestamp), which
+	/// identifies the window's secret.
+	pub window_start: Option<i64>,
+	/// Seconds between QR rotations.  Informational: freshness is always
+	/// judged against [`QR_MAX_AGE`].
+	pub period: Option<u32>,
+	/// Network the event lives on.
+	pub network: Option<String>,
 }
 
 impl QrPayload {
-	/// Parse the pipe-delimited QR string: `event_id|timestamp|hmac`.
+	/// Version written by [`QrPayload::encode`] and [`QrPayload::to_url`].
+	pub const VERSION: u32 = 2;
+	/// The original three-field format.
+	pub const LEGACY_VERSION: u32 = 1;
+
+	/// Parse QR data in either encoding: a deep link such as
+	/// `https://ckb-pop.xyz/a?e=<event_id>&t=<timestamp>&h=<hmac>&v=2`, or
+	/// the pipe-delimited `v2|event_id|timestamp|hmac|key=value|...`.
+	/// Version 1 codes (`event_id|timestamp|hmac`) are still accepted.
 	pub fn parse(data: &str) -> Option<Self> {
-		let mut parts = data.splitn(3, '|');
+		let data = data.trim();
+		if data.starts_with("https://") || data.starts_with("http://") {
+			return Self::parse_url(data);
+		}
+		let tagged = data.split_once('|').and_then(|(tag, rest)| {
+			let version: u32 = tag.strip_prefix('v')?.parse().ok()?;
+			(version >= Self::VERSION).then_some((version, rest))
+		});
+		let payload = match tagged {
+			Some((version, rest)) => Self::parse_pipe_tagged(version, rest)?,
+			None => Self::parse_pipe_v1(data)?,
+		};
+		payload.is_valid().then_some(payload)
+	}
+
+	/// `event_id|timestamp|hmac`.
+	fn parse_pipe_v1(data: &str) -> Option<Self> {
+		let mut parts = data.split('|');
 		let event_id = parts.next()?.to_owned();
 		let timestamp: i64 = parts.next()?.parse().ok()?;
 		let hmac = parts.next()?.to_owned();
-		if event_id.is_empty() || hmac.is_empty() {
+		if parts.next().is_some() {
 			return None;
 		}
-		Some(Self { event_id, timestamp, hmac })
+		Some(Self::new(Self::LEGACY_VERSION, event_id, timestamp, hmac))
+	}
+
+	/// `event_id|timestamp|hmac` followed by optional `key=value` fields.
+	fn parse_pipe_tagged(version: u32, data: &str) -> Option<Self> {
+		let mut parts = data.split('|');
+		let event_id = parts.next()?.to_owned();
+		let timestamp: i64 = parts.next()?.parse().ok()?;
+		let hmac = parts.next()?.to_owned();
+		let mut payload = Self::new(version, event_id, timestamp, hmac);
+		for field in parts {
+			let (key, value) = field.split_once('=')?;
+			payload.set_field(key, value)?;
+		}
+		Some(payload)
+	}
+
+	fn parse_url(data: &str) -> Option<Self> {
+		let url = reqwest::Url::parse(data).ok()?;
+		// Only the attend page of a frontend, wherever that is mounted.
+		if !url.path().ends_with(QR_URL_PATH) {
+			return None;
+		}
+		let field = |key: &str| {
+			url.query_pairs()
+				.find(|(k, _)| k == key)
+				.map(|(_, v)| v.into_owned())
+		};
+		let version: u32 = field("v")?.parse().ok()?;
+		if version < Self::VERSION {
+			return None;
+		}
+		let timestamp: i64 = field("t")?.parse().ok()?;
+		let mut payload = Self::new(version, field("e")?, timestamp, field("h")?);
+		for (key, value) in url.query_pairs() {
+			if !matches!(key.as_ref(), "v" | "e" | "t" | "h") {
+				payload.set_field(&key, &value)?;
+			}
+		}
+		payload.is_valid().then_some(payload)
+	}
+
+	fn new(version: u32, event_id: String, timestamp: i64, hmac: String) -> Self {
+		Self {
+			version,
+			event_id,
+			timestamp,
+			hmac,
+			issuer: None,
+			window_start: None,
+			period: None,
+			network: None,
+		}
+	}
+
+	/// Set an optional field from its short key.  Unknown keys are ignored
+	/// so that newer codes still parse; `None` if a known value is malformed.
+	fn set_field(&mut self, key: &str, value: &str) -> Option<()> {
+		match key {
+			"i" => self.issuer = Some(value.to_owned()),
+			"w" => self.window_start = Some(value.parse().ok()?),
+			"p" => self.period = Some(value.parse().ok().filter(|&p: &u32| p > 0)?),
+			"n" => self.network = Some(value.to_owned()),
+			_ => {}
+		}
+		Some(())
+	}
+
+	/// Optional fields as `(key, value)`, in encoding order.
+	fn fields(&self) -> Vec<(&'static str, String)> {
+		let mut fields = Vec::new();
+		if let Some(window_start) = self.window_start {
+			fields.push(("w", window_start.to_string()));
+		}
+		if let Some(period) = self.period {
+			fields.push(("p", period.to_string()));
+		}
+		if let Some(issuer) = &self.issuer {
+			fields.push(("i", issuer.clone()));
+		}
+		if let Some(network) = &self.network {
+			fields.push(("n", network.clone()));
+		}
+		fields
+	}
+
+	/// The optional fields covered by the HMAC, as `k=v|k=v`.  Empty for
+	/// version 1 codes, whose HMAC covers only the timestamp.
+	pub fn authenticated_fields(&self) -> String {
+		if self.version == Self::LEGACY_VERSION {
+			return String::new();
+		}
+		self.fields()
+			.iter()
+			.map(|(key, value)| format!("{key}={value}"))
+			.collect::<Vec<_>>()
+			.join("|")
+	}
+
+	/// Set the HMAC for the payload's timestamp and optional fields.
+	pub fn sign(&mut self, window_secret: &[u8; 32]) {
+		self.hmac = generate_qr_hmac(window_secret, self.timestamp, &self.authenticated_fields());
+	}
+
+	/// Check that the code is at most [`QR_MAX_AGE`] old at `now`, and not
+	/// dated before its window opened.
+	pub fn check_fresh(&self, now: i64) -> Result<()> {
+		let age = now - self.timestamp;
+		if !(0..=QR_MAX_AGE).contains(&age) {
+			anyhow::bail!("QR code expired ({age}s old, maximum is {QR_MAX_AGE}s).");
+		}
+		// Timestamps are rounded down to the period, so the first code of a
+		// window may be up to one period older than the window.
+		if self
+			.window_start
+			.is_some_and(|start| self.timestamp <= start - i64::from(QR_PERIOD))
+		{
+			anyhow::bail!("QR code is dated before its attendance window opened.");
+		}
+		Ok(())
+	}
+
+	fn is_valid(&self) -> bool {
+		let empty = |f: &Option<String>| f.as_deref() == Some("");
+		is_event_id(&self.event_id)
+			&& !(self.hmac.is_empty() || empty(&self.issuer) || empty(&self.network))
 	}
 
-	/// Encode back to the pipe-delimited format.
-	#[allow(dead_code)]
+	/// Encode as a deep link to the attend page under `base` (the frontend
+	/// URL), which phone cameras open in a browser.
+	pub fn to_url(&self, base: &str) -> String {
+		let mut url = format!("{}{QR_URL_PATH}", base.trim_end_matches('/'));
+		let mut query = reqwest::Url::parse("https://x/").expect("static URL parses");
+		{
+			let mut pairs = query.query_pairs_mut();
+			pairs
+				.append_pair("e", &self.event_id)
+				.append_pair("t", &self.timestamp.to_string())
+				.append_pair("h", &self.hmac);
+			for (key, value) in self.fields() {
+				pairs.append_pair(key, &value);
+			}
+			pairs.append_pair("v", &self.version.to_string());
+		}
+		url.push('?');
+		url.push_str(query.query().unwrap_or_default());
+		url
+	}
+
+	/// Encode to the pipe-delimited format of the payload's version.
 	pub fn encode(&self) -> String {
-		format!("{}|{}|{}", self.event_id, self.timestamp, self.hmac)
+		let mut data = format!("{}|{}|{}", self.event_id, self.timestamp, self.hmac);
+		if self.version == Self::LEGACY_VERSION {
+			return data;
+		}
+		data.insert_str(0, &format!("v{}|", self.version));
+		for (key, value) in self.fields() {
+			data.push_str(&format!("|{key}={value}"));
+		}
+		data
 	}
 }
 
@@ -74,41 +290,138 @@ pub fn derive_window_secret(event_id: &str, window_start: i64, creator_sig: &str
 }
 
 /// Produce the 16-hex-character HMAC that goes into each rotating QR code.
-pub fn generate_qr_hmac(window_secret: &[u8; 32], timestamp: i64) -> String {
+/// It covers the timestamp and `fields`, the payload's optional fields from
+/// [`QrPayload::authenticated_fields`]; with no fields it is the version 1
+/// HMAC of the timestamp alone.
+pub fn generate_qr_hmac(window_secret: &[u8; 32], timestamp: i64, fields: &str) -> String {
 	let mut mac =
 		HmacSha256::new_from_slice(window_secret).expect("HMAC-SHA256 accepts any key length");
 	mac.update(&timestamp.to_le_bytes());
+	if !fields.is_empty() {
+		mac.update(b"|");
+		mac.update(fields.as_bytes());
+	}
 	let full = hex::encode(mac.finalize().into_bytes());
 	full[..16].to_string()
 }
 
-/// Verify a QR HMAC against the window secret and timestamp.
-#[allow(dead_code)]
-pub fn verify_qr_hmac(window_secret: &[u8; 32], timestamp: i64, expected: &str) -> bool {
-	generate_qr_hmac(window_secret, timestamp) == expected
+// -- Canonical hashing --
+
+/// Version byte of the canonical hash encoding.  It is hashed in front of
+/// the canonical JSON, so digests of a future encoding can never collide
+/// with these, nor with the unprefixed hashes ckb-pop.xyz recomputes.
+pub const CANONICAL_HASH_V1: u8 = 0x01;
+
+/// Largest integer ECMAScript numbers represent exactly (2^53 - 1).
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Serialize `value` per RFC 8785 (JSON Canonicalization Scheme): no
+/// whitespace, object members sorted by their UTF-16 code units, and
+/// strings escaped as ECMAScript's `JSON.stringify` does.
+///
+/// Numbers must be integers within +/-(2^53 - 1), which JCS renders as
+/// plain decimals.  PoP documents hold no other numbers, so fractions and
+/// larger values are rejected rather than risk a divergent rendering.
+pub fn canonical_json(value: &Value) -> Result<String> {
+	let mut out = String::new();
+	write_canonical(value, &mut out)?;
+	Ok(out)
+}
+
+/// `SHA256(CANONICAL_HASH_V1 || canonical_json(value))`.
+pub fn canonical_hash(value: &Value) -> Result<[u8; 32]> {
+	let mut h = Sha256::new();
+	h.update([CANONICAL_HASH_V1]);
+	h.update(canonical_json(value)?.as_bytes());
+	Ok(h.finalize().into())
+}
+
+fn write_canonical(value: &Value, out: &mut String) -> Result<()> {
+	match value {
+		Value::Null => out.push_str("null"),
+		Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+		Value::Number(n) => {
+			let int = n
+				.as_i64()
+				.filter(|i| i.unsigned_abs() <= MAX_SAFE_INTEGER)
+				.map(|i| i.to_string())
+				.or_else(|| n.as_u64().filter(|u| *u <= MAX_SAFE_INTEGER).map(|u| u.to_string()))
+				.ok_or_else(|| anyhow!("canonical JSON only supports safe integers, got {n}"))?;
+			out.push_str(&int);
+		}
+		Value::String(s) => write_canonical_string(s, out),
+		Value::Array(items) => {
+			out.push('[');
+			for (i, item) in items.iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_canonical(item, out)?;
+			}
+			out.push(']');
+		}
+		Value::Object(members) => {
+			let mut members: Vec<_> = members.iter().collect();
+			members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+			out.push('{');
+			for (i, (key, item)) in members.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_canonical_string(key, out);
+				out.push(':');
+				write_canonical(item, out)?;
+			}
+			out.push('}');
+		}
+	}
+	Ok(())
+}
+
+fn write_canonical_string(s: &str, out: &mut String) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\u{08}' => out.push_str("\\b"),
+			'\u{0c}' => out.push_str("\\f"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
 }
 
 // -- Cell data builders --
 
+/// The document a dob-badge's content hash commits to.
+pub fn badge_content(event_id: &str, issuer: &str, proof_hash: Option<&str>) -> Value {
+	let mut content = serde_json::json!({
+		"protocol": "ckb-pop",
+		"version": 1,
+		"event_id": event_id,
+		"issuer": issuer,
+	});
+	if let Some(ph) = proof_hash {
+		content["proof_hash"] = ph.into();
+	}
+	content
+}
+
 /// Build the 34-byte binary cell data for a dob-badge output:
-/// `[version: u8 | flags: u8 | content_hash: 32 bytes]`.
+/// `[version: u8 | flags: u8 | content_hash: 32 bytes]`, where the content
+/// hash is SHA-256 of the [`canonical_json`] of [`badge_content`], without
+/// the [`CANONICAL_HASH_V1`] prefix.  Those are the bytes ckb-pop.xyz
+/// recomputes; badges keep them until the website adopts [`canonical_hash`].
 pub fn build_badge_cell_data(event_id: &str, issuer: &str, proof_hash: Option<&str>) -> Vec<u8> {
-	let content = match proof_hash {
-		Some(ph) => serde_json::json!({
-			"protocol": "ckb-pop",
-			"version": 1,
-			"event_id": event_id,
-			"issuer": issuer,
-			"proof_hash": ph,
-		}),
-		None => serde_json::json!({
-			"protocol": "ckb-pop",
-			"version": 1,
-			"event_id": event_id,
-			"issuer": issuer,
-		}),
-	};
-	let content_hash = sha256(serde_json::to_string(&content).unwrap().as_bytes());
+	let content = badge_content(event_id, issuer, proof_hash);
+	let json =
+		canonical_json(&content).expect("badge content holds only strings and small integers");
+	let content_hash = sha256(json.as_bytes());
 
 	let mut data = Vec::with_capacity(34);
 	data.push(0x01); // version
@@ -117,6 +430,33 @@ pub fn build_badge_cell_data(event_id: &str, issuer: &str, proof_hash: Option<&s
 	data
 }
 
+/// Decoded form of the 34-byte dob-badge cell data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadgeCellData {
+	pub version: u8,
+	pub flags: u8,
+	pub content_hash: [u8; 32],
+}
+
+impl BadgeCellData {
+	/// Flag bit set when the content hash covers badge metadata.
+	pub const FLAG_HAS_METADATA: u8 = 0x01;
+
+	/// Parse raw cell data; `None` if it is not exactly 34 bytes.
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		if data.len() != 34 {
+			return None;
+		}
+		let mut content_hash = [0u8; 32];
+		content_hash.copy_from_slice(&data[2..]);
+		Some(Self {
+			version: data[0],
+			flags: data[1],
+			content_hash,
+		})
+	}
+}
+
 /// Build JSON cell data for an event-anchor output.
 pub fn build_anchor_cell_data(
 	event_id: &str,
@@ -144,6 +484,12 @@ pub fn attendance_message(event_id: &str, qr_timestamp: i64, attendee_address: &
 	format!("CKB-PoP|{event_id}|{qr_timestamp}|{attendee_address}")
 }
 
+/// Hash of an attendance signature, committed into the badge content so
+/// the badge can be tied back to the signed proof.
+pub fn proof_hash(signature: &str) -> String {
+	sha256_hex(signature.as_bytes())
+}
+
 /// The message an event creator signs to open an attendance window.
 pub fn window_message(event_id: &str, window_start: i64, window_end: Option<i64>) -> String {
 	let end_part = match window_end {
@@ -153,12 +499,107 @@ pub fn window_message(event_id: &str, window_start: i64, window_end: Option<i64>
 	format!("CKB-PoP-Window|{event_id}|{window_start}|{end_part}")
 }
 
+/// Message an organizer signs once to open every window of a schedule.
+/// `windows` are `(start, end)` Unix timestamps in order.
+pub fn schedule_message(event_id: &str, windows: &[(i64, i64)]) -> String {
+	let windows = windows
+		.iter()
+		.map(|(start, end)| format!("{start}-{end}"))
+		.collect::<Vec<_>>()
+		.join(",");
+	format!("CKB-PoP-Schedule|{event_id}|{windows}")
+}
+
+/// Message `creator` signs to let `delegate` open windows for the event on
+/// `network`, optionally until `expires_at` (Unix timestamp).
+pub fn delegation_message(
+	network: &str,
+	event_id: &str,
+	creator: &str,
+	delegate: &str,
+	expires_at: Option<i64>,
+) -> String {
+	let expiry = match expires_at {
+		Some(ts) => ts.to_string(),
+		None => "never".into(),
+	};
+	format!("CKB-PoP-Delegate|{network}|{event_id}|{creator}|{delegate}|{expiry}")
+}
+
+// -- Signature verification --
+
+/// Outcome of checking a wallet signature against an address.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureCheck {
+	/// The signature recovers to the key behind the address's lock.
+	Valid,
+	/// The signature is malformed or belongs to a different key.
+	Invalid,
+	/// The address uses a lock whose signing scheme cannot be checked
+	/// offline (JoyID, Omnilock, EVM wallets, ...).
+	Unsupported(String),
+}
+
+/// Verify a message signature produced by a CKB secp256k1 wallet.
+///
+/// Wallets following the CCC convention sign
+/// `blake2b("Nervos Message:" || message)` and return `r || s || v` as
+/// 65 bytes of hex.  The recovered public key must hash (blake160) to the
+/// args of the address's default sighash lock.
+pub fn verify_message_signature(
+	message: &str,
+	signature: &str,
+	address: &str,
+) -> Result<SignatureCheck> {
+	let addr: ckb_sdk::Address = address
+		.parse()
+		.map_err(|e| anyhow!("invalid address: {e}"))?;
+	let lock: Script = (&addr).into();
+
+	let is_sighash = lock.code_hash() == ckb_sdk::constants::SIGHASH_TYPE_HASH.pack()
+		&& lock.hash_type() == ScriptHashType::Type.into();
+	if !is_sighash {
+		return Ok(SignatureCheck::Unsupported(format!(
+			"lock code hash 0x{}",
+			hex::encode(lock.code_hash().raw_data())
+		)));
+	}
+
+	let raw = match hex::decode(signature.strip_prefix("0x").unwrap_or(signature)) {
+		Ok(raw) if raw.len() == 65 => raw,
+		_ => return Ok(SignatureCheck::Invalid),
+	};
+	let Ok(sig) = ckb_crypto::secp::Signature::from_slice(&raw) else {
+		return Ok(SignatureCheck::Invalid);
+	};
+	let Ok(pubkey) = sig.recover(&H256(message_digest(message))) else {
+		return Ok(SignatureCheck::Invalid);
+	};
+
+	let blake160 = &ckb_hash::blake2b_256(pubkey.serialize())[..20];
+	if blake160 == lock.args().raw_data().as_ref() {
+		Ok(SignatureCheck::Valid)
+	} else {
+		Ok(SignatureCheck::Invalid)
+	}
+}
+
+/// The 32-byte digest a CKB secp256k1 wallet signs for a text message.
+fn message_digest(message: &str) -> [u8; 32] {
+	ckb_hash::blake2b_256(format!("Nervos Message:{message}"))
+}
+
 // -- Utility --
 
 fn sha256(data: &[u8]) -> [u8; 32] {
 	Sha256::digest(data).into()
 }
 
+/// Hex-encoded SHA256, for digests that end up inside signed text.
+pub fn sha256_hex(data: &[u8]) -> String {
+	hex::encode(sha256(data))
+}
+
 /// First 20 bytes of SHA256 — used for type script args to match the
 /// on-chain contracts' 20-byte truncated hash layout.
 fn sha256_truncated(data: &[u8]) -> [u8; 20] {
@@ -168,10 +609,38 @@ fn sha256_truncated(data: &[u8]) -> [u8; 20] {
 	out
 }
 
+/// Sign `message` the way a CCC secp256k1 wallet would and return the
+/// signature alongside the matching testnet address.
+#[cfg(test)]
+pub(crate) fn wallet_sign(seed: u8, message: &str) -> (String, String) {
+	let privkey = ckb_crypto::secp::Privkey::from_slice(&[seed; 32]);
+	let pubkey = privkey.pubkey().unwrap();
+	let blake160 = &ckb_hash::blake2b_256(pubkey.serialize())[..20];
+	let payload = ckb_sdk::AddressPayload::new_short(
+		ckb_sdk::CodeHashIndex::Sighash,
+		ckb_types::H160::from_slice(blake160).unwrap(),
+	);
+	let address = ckb_sdk::Address::new(ckb_sdk::NetworkType::Testnet, payload, true);
+	let sig = privkey
+		.sign_recoverable(&H256(message_digest(message)))
+		.unwrap();
+	(format!("0x{}", hex::encode(sig.serialize())), address.to_string())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn badge_cell_data_parses() {
+		let data = build_badge_cell_data("evt", "ckt1qissuer", Some("ab"));
+		let parsed = BadgeCellData::parse(&data).unwrap();
+		assert_eq!(parsed.version, 1);
+		assert_eq!(parsed.flags, BadgeCellData::FLAG_HAS_METADATA);
+		assert_eq!(parsed.content_hash[..], data[2..]);
+		assert!(BadgeCellData::parse(&data[..33]).is_none());
+	}
+
 	#[test]
 	fn event_id_is_deterministic() {
 		let a = compute_event_id("ckt1qtest", 1_700_000_000, "nonce1");
@@ -195,33 +664,206 @@ mod tests {
 		assert_eq!(args.len(), 40);
 	}
 
-	#[test]
-	fn qr_payload_roundtrip() {
-		let original = QrPayload {
-			event_id: "abc123".into(),
+	const EVENT_ID: &str = "abc1230000000000000000000000000000000000000000000000000000000000";
+
+	/// A version 2 payload with every field set.
+	fn full_payload() -> QrPayload {
+		QrPayload {
+			version: QrPayload::VERSION,
+			event_id: EVENT_ID.into(),
 			timestamp: 1_700_000_000,
 			hmac: "deadbeef01234567".into(),
-		};
-		let encoded = original.encode();
-		let parsed = QrPayload::parse(&encoded).unwrap();
-		assert_eq!(parsed, original);
+			issuer: Some("ckt1qorganizer".into()),
+			window_start: Some(1_699_999_940),
+			period: Some(30),
+			network: Some("testnet".into()),
+		}
+	}
+
+	#[test]
+	fn qr_payload_roundtrip() {
+		let payload = full_payload();
+		let encoded = payload.encode();
+		assert_eq!(
+			encoded,
+			format!(
+				"v2|{EVENT_ID}|1700000000|deadbeef01234567|w=1699999940|p=30|i=ckt1qorganizer|n=testnet"
+			)
+		);
+		assert_eq!(QrPayload::parse(&encoded).unwrap(), payload);
+
+		let bare = QrPayload::parse(&format!("v2|{EVENT_ID}|1700000000|deadbeef01234567")).unwrap();
+		assert_eq!(bare.version, 2);
+		assert_eq!((bare.window_start, bare.period, bare.network), (None, None, None));
+	}
+
+	#[test]
+	fn qr_payload_reads_version_1_codes() {
+		let legacy = QrPayload::parse(&format!("{EVENT_ID}|1700000000|deadbeef01234567")).unwrap();
+		assert_eq!(legacy.version, QrPayload::LEGACY_VERSION);
+		assert_eq!(legacy.event_id, EVENT_ID);
+		assert_eq!(legacy.issuer, None);
+		assert_eq!(legacy.encode(), format!("{EVENT_ID}|1700000000|deadbeef01234567"));
+
+		// Only the original three fields; anything more needs a version tag.
+		let delegated = format!("{EVENT_ID}|1700000000|deadbeef01234567|ckt1qorganizer");
+		assert!(QrPayload::parse(&delegated).is_none());
+		// Deep links were introduced with version 2.
+		let link = format!("https://ckb-pop.xyz/a?e={EVENT_ID}&t=1700000000&h=deadbeef01234567&v=1");
+		assert!(QrPayload::parse(&link).is_none());
+	}
+
+	#[test]
+	fn qr_payload_ignores_unknown_fields() {
+		let newer = format!("v3|{EVENT_ID}|1700000000|deadbeef01234567|n=testnet|x=future");
+		let parsed = QrPayload::parse(&newer).unwrap();
+		assert_eq!(parsed.version, 3);
+		assert_eq!(parsed.network.as_deref(), Some("testnet"));
+
+		let link = format!("https://ckb-pop.xyz/a?e={EVENT_ID}&t=1700000000&h=deadbeef01234567&x=1&v=2");
+		assert_eq!(QrPayload::parse(&link).unwrap().event_id, EVENT_ID);
 	}
 
 	#[test]
 	fn qr_payload_rejects_garbage() {
+		let parse = |data: String| QrPayload::parse(&data);
 		assert!(QrPayload::parse("").is_none());
 		assert!(QrPayload::parse("only|two").is_none());
-		assert!(QrPayload::parse("a|notanumber|c").is_none());
+		assert!(parse(format!("{EVENT_ID}|notanumber|c")).is_none());
 		assert!(QrPayload::parse("|123|hmac").is_none());
+		assert!(parse(format!("{EVENT_ID}|123|hmac|")).is_none());
+		// The event ID must be 64 hex characters.
+		assert!(QrPayload::parse("abc123|123|hmac").is_none());
+		assert!(parse(format!("../../{}|123|hmac", &EVENT_ID[6..])).is_none());
+		assert!(parse(format!("{}é|123|hmac", &EVENT_ID[2..])).is_none());
+		assert!(parse(format!("v2|{EVENT_ID}0|123|hmac")).is_none());
+		// Version 2 fields must be well formed.
+		assert!(parse(format!("v2|{EVENT_ID}|123|hmac|")).is_none());
+		assert!(parse(format!("v2|{EVENT_ID}|123|hmac|w=soon")).is_none());
+		assert!(parse(format!("v2|{EVENT_ID}|123|hmac|p=0")).is_none());
+		assert!(parse(format!("v2|{EVENT_ID}|123|hmac|n=")).is_none());
+		// Deep links need every field and a known version.
+		let link = "https://ckb-pop.xyz/a";
+		assert!(parse(format!("{link}?e={EVENT_ID}&t=1&h=ff")).is_none());
+		assert!(parse(format!("{link}?e={EVENT_ID}&t=1&h=ff&v=0")).is_none());
+		assert!(parse(format!("{link}?e={EVENT_ID}&t=x&h=ff&v=2")).is_none());
+		assert!(parse(format!("{link}?e={EVENT_ID}&t=1&h=ff&p=x&v=2")).is_none());
+		assert!(parse(format!("{link}?e=abc&t=1&h=ff&v=2")).is_none());
+		// ...and must point at an attend page.
+		assert!(parse(format!("https://ckb-pop.xyz/?e={EVENT_ID}&t=1&h=ff&v=2")).is_none());
+		assert!(parse(format!("https://ckb-pop.xyz/pay?e={EVENT_ID}&t=1&h=ff&v=2")).is_none());
+	}
+
+	#[test]
+	fn qr_payload_deep_link_roundtrip() {
+		let payload = full_payload();
+		let url = payload.to_url("https://ckb-pop.xyz/");
+		assert_eq!(
+			url,
+			format!(
+				"https://ckb-pop.xyz/a?e={EVENT_ID}&t=1700000000&h=deadbeef01234567\
+				 &w=1699999940&p=30&i=ckt1qorganizer&n=testnet&v=2"
+			)
+		);
+		assert_eq!(QrPayload::parse(&url).unwrap(), payload);
+
+		let url = payload.to_url("http://127.0.0.1:5173/pop");
+		assert!(url.starts_with("http://127.0.0.1:5173/pop/a?"), "{url}");
+		assert_eq!(QrPayload::parse(&url).unwrap(), payload);
+		// Field order does not matter.
+		let reordered =
+			format!("https://ckb-pop.xyz/a?v=2&h=deadbeef01234567&n=testnet&e={EVENT_ID}&t=1700000000");
+		let parsed = QrPayload::parse(&reordered).unwrap();
+		assert_eq!(parsed.event_id, EVENT_ID);
+		assert_eq!(parsed.network.as_deref(), Some("testnet"));
 	}
 
 	#[test]
 	fn hmac_roundtrip() {
 		let secret = derive_window_secret("evt1", 1_700_000_000, "sig123");
-		let hmac = generate_qr_hmac(&secret, 1_700_000_030);
+		let hmac = generate_qr_hmac(&secret, 1_700_000_030, "");
 		assert_eq!(hmac.len(), 16);
-		assert!(verify_qr_hmac(&secret, 1_700_000_030, &hmac));
-		assert!(!verify_qr_hmac(&secret, 1_700_000_031, &hmac));
+		assert_eq!(generate_qr_hmac(&secret, 1_700_000_030, ""), hmac);
+		assert_ne!(generate_qr_hmac(&secret, 1_700_000_031, ""), hmac);
+		assert_ne!(generate_qr_hmac(&secret, 1_700_000_030, "p=30"), hmac);
+	}
+
+	#[test]
+	fn qr_hmac_covers_the_optional_fields() {
+		let secret = derive_window_secret(EVENT_ID, 1_699_999_940, "sig123");
+		let mut payload = full_payload();
+		payload.sign(&secret);
+
+		for tamper in ["w=1", "p=86400", "i=ckt1qsomeoneelse", "n=mainnet"] {
+			let (key, value) = tamper.split_once('=').unwrap();
+			let mut edited = payload.clone();
+			edited.set_field(key, value).unwrap();
+			edited.sign(&secret);
+			assert_ne!(edited.hmac, payload.hmac, "{tamper}");
+		}
+
+		// Version 1 codes keep the timestamp-only HMAC.
+		let mut legacy = QrPayload::parse(&format!("{EVENT_ID}|1700000000|00")).unwrap();
+		legacy.sign(&secret);
+		assert_eq!(legacy.hmac, generate_qr_hmac(&secret, 1_700_000_000, ""));
+	}
+
+	#[test]
+	fn qr_freshness_ignores_the_advertised_period() {
+		let mut payload = full_payload();
+		let now = payload.timestamp + QR_MAX_AGE;
+		assert!(payload.check_fresh(now).is_ok());
+		assert!(payload.check_fresh(now + 1).is_err());
+		assert!(payload.check_fresh(payload.timestamp - 1).is_err());
+
+		payload.period = Some(86_400);
+		assert!(payload.check_fresh(payload.timestamp + 120).is_err());
+
+		payload.window_start = Some(payload.timestamp + 30);
+		assert!(payload.check_fresh(payload.timestamp).is_err());
+	}
+
+	#[test]
+	fn canonical_json_sorts_by_utf16_and_escapes_like_ecmascript() {
+		// Member ordering example from RFC 8785, section 3.2.3.
+		let value = serde_json::json!({
+			"\u{20ac}": "Euro Sign",
+			"\r": "Carriage Return",
+			"\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+			"1": "One",
+			"\u{1f600}": "Emoji: Grinning Face",
+			"\u{80}": "Control",
+			"\u{f6}": "Latin Small Letter O With Diaeresis",
+		});
+		assert_eq!(
+			canonical_json(&value).unwrap(),
+			"{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+			 \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+			 \"\u{1f600}\":\"Emoji: Grinning Face\",\
+			 \"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+		);
+
+		let value = serde_json::json!({
+			"b": [1, -2, null, true, false],
+			"a": "quote\" slash\\ /\u{1}\u{8}\t\u{1f}é",
+		});
+		assert_eq!(
+			canonical_json(&value).unwrap(),
+			r#"{"a":"quote\" slash\\ /\u0001\b\t\u001fé","b":[1,-2,null,true,false]}"#
+		);
+
+		assert!(canonical_json(&serde_json::json!({ "n": 1.5 })).is_err());
+		assert!(canonical_json(&serde_json::json!({ "n": 1u64 << 53 })).is_err());
+	}
+
+	#[test]
+	fn badge_content_hash_golden_vector() {
+		// SHA256({"event_id":"evt1","issuer":"ckt1qissuer","proof_hash":"ab","protocol":"ckb-pop","version":1})
+		let data = build_badge_cell_data("evt1", "ckt1qissuer", Some("ab"));
+		assert_eq!(
+			hex::encode(&data[2..]),
+			"f29bfd1fd33b83890d10aea3dad65bfab82cbe3604326eb21656a61dd07385de"
+		);
 	}
 
 	#[test]
@@ -244,9 +886,44 @@ mod tests {
 		assert_eq!(msg, "CKB-PoP-Window|EVT001|1700000000|open");
 	}
 
+	#[test]
+	fn wallet_signature_verifies() {
+		let msg = attendance_message("EVT001", 1_700_000_000, "ckt1qaddr");
+		let (sig, address) = wallet_sign(7, &msg);
+		let check = verify_message_signature(&msg, &sig, &address).unwrap();
+		assert_eq!(check, SignatureCheck::Valid);
+	}
+
+	#[test]
+	fn wallet_signature_rejects_other_message_or_key() {
+		let msg = attendance_message("EVT001", 1_700_000_000, "ckt1qaddr");
+		let (sig, address) = wallet_sign(7, &msg);
+		let (_, other_address) = wallet_sign(8, &msg);
+
+		let tampered = attendance_message("EVT001", 1_700_000_030, "ckt1qaddr");
+		assert_eq!(
+			verify_message_signature(&tampered, &sig, &address).unwrap(),
+			SignatureCheck::Invalid
+		);
+		assert_eq!(
+			verify_message_signature(&msg, &sig, &other_address).unwrap(),
+			SignatureCheck::Invalid
+		);
+		assert_eq!(
+			verify_message_signature(&msg, "0xdead", &address).unwrap(),
+			SignatureCheck::Invalid
+		);
+	}
+
 	#[test]
 	fn window_message_bounded() {
 		let msg = window_message("EVT001", 1_700_000_000, Some(1_700_003_600));
 		assert_eq!(msg, "CKB-PoP-Window|EVT001|1700000000|1700003600");
 	}
+
+	#[test]
+	fn schedule_message_lists_every_window() {
+		let msg = schedule_message("EVT001", &[(1_700_000_000, 1_700_003_600), (1_700_086_400, 1_700_090_000)]);
+		assert_eq!(msg, "CKB-PoP-Schedule|EVT001|1700000000-1700003600,1700086400-1700090000");
+	}
 }
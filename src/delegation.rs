@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::crypto::{self, SignatureCheck};
+
+/// An event creator's signed permission for another address to open
+/// attendance windows for the event.  Badges minted through a delegated
+/// window name the delegate as their issuer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delegation {
+	/// Delegation format version.
+	pub version: u8,
+	pub network: String,
+	pub event_id: String,
+	pub creator_address: String,
+	pub delegate_address: String,
+	/// Unix timestamp after which the delegation no longer applies.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub expires_at: Option<i64>,
+	/// Creator's signature over [`Delegation::message`].
+	pub signature: String,
+}
+
+impl Delegation {
+	pub const VERSION: u8 = 2;
+
+	/// The message the creator signs.  Every field but the signature is
+	/// covered, so a delegation cannot be moved to another network or
+	/// credited to another creator.
+	pub fn message(&self) -> String {
+		crypto::delegation_message(
+			&self.network,
+			&self.event_id,
+			&self.creator_address,
+			&self.delegate_address,
+			self.expires_at,
+		)
+	}
+
+	/// Check that the delegation is unexpired at `now` and signed by its
+	/// creator.  A delegation whose signature cannot be checked, because the
+	/// creator's lock is not the default secp256k1 one, is refused: it would
+	/// otherwise let anyone claim to be a co-organizer.
+	pub fn verify(&self, now: i64) -> Result<()> {
+		if self.version != Self::VERSION {
+			anyhow::bail!(
+				"the delegation to {} is in format version {}; ask the creator to sign it again",
+				self.delegate_address,
+				self.version
+			);
+		}
+		if let Some(expires_at) = self.expires_at {
+			if now >= expires_at {
+				anyhow::bail!("the delegation to {} has expired", self.delegate_address);
+			}
+		}
+		match crypto::verify_message_signature(&self.message(), &self.signature, &self.creator_address)? {
+			SignatureCheck::Valid => Ok(()),
+			SignatureCheck::Invalid => anyhow::bail!(
+				"the delegation to {} is not signed by the event creator {}",
+				self.delegate_address,
+				self.creator_address
+			),
+			SignatureCheck::Unsupported(lock) => anyhow::bail!(
+				"the delegation to {} cannot be checked: the creator {} uses an unsupported lock \
+				 ({lock})",
+				self.delegate_address,
+				self.creator_address
+			),
+		}
+	}
+
+	/// Check that the delegation lets `delegate` open windows for `event_id`
+	/// on `network`, that it was granted by the event's `creator`, and that
+	/// it was valid at `at`; see [`Delegation::verify`].
+	pub fn check(
+		&self,
+		network: &str,
+		event_id: &str,
+		creator: &str,
+		delegate: &str,
+		at: i64,
+	) -> Result<()> {
+		if self.network != network || self.event_id != event_id || self.delegate_address != delegate {
+			anyhow::bail!("the delegation is for another event, network or address");
+		}
+		if self.creator_address != creator {
+			anyhow::bail!(
+				"the delegation is signed by {}, but event {event_id} was created by {creator}",
+				self.creator_address
+			);
+		}
+		self.verify(at)
+	}
+
+	/// Directory where delegations are stored (~/.ckb-pop/delegations/).
+	pub fn dir() -> PathBuf {
+		Config::dir().join("delegations")
+	}
+
+	/// Write the delegation into the delegations directory and return its
+	/// path.  A newer delegation to the same address replaces the older.
+	pub fn save(&self) -> Result<PathBuf> {
+		let dir = Self::dir();
+		std::fs::create_dir_all(&dir)?;
+		let delegate = crypto::sha256_hex(self.delegate_address.as_bytes());
+		let name = format!("{}-{}-{}.json", self.network, self.event_id, &delegate[..16]);
+		let path = dir.join(name);
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(path)
+	}
+
+	pub fn load(path: &Path) -> Result<Self> {
+		let content = std::fs::read_to_string(path)
+			.with_context(|| format!("could not read {}", path.display()))?;
+		serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid delegation", path.display()))
+	}
+
+	/// The stored delegation of `event_id` on `network` to `delegate`, if any.
+	pub fn find(network: &str, event_id: &str, delegate: &str) -> Result<Option<Self>> {
+		let dir = Self::dir();
+		if !dir.exists() {
+			return Ok(None);
+		}
+		for entry in std::fs::read_dir(&dir)? {
+			let path = entry?.path();
+			if path.extension().is_none_or(|e| e != "json") {
+				continue;
+			}
+			// Skip unreadable files rather than failing on unrelated ones.
+			let Ok(d) = Self::load(&path) else { continue };
+			if d.network == network && d.event_id == event_id && d.delegate_address == delegate {
+				return Ok(Some(d));
+			}
+		}
+		Ok(None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A delegation signed by the wallet with key `seed`.
+	fn signed(seed: u8, expires_at: Option<i64>) -> Delegation {
+		let mut delegation = Delegation {
+			version: Delegation::VERSION,
+			network: "testnet".into(),
+			event_id: "ab".repeat(32),
+			creator_address: crypto::wallet_sign(seed, "").1,
+			delegate_address: "ckt1qdelegate".into(),
+			expires_at,
+			signature: String::new(),
+		};
+		delegation.signature = crypto::wallet_sign(seed, &delegation.message()).0;
+		delegation
+	}
+
+	#[test]
+	fn creator_signature_is_checked() {
+		let delegation = signed(7, None);
+		assert!(delegation.verify(1_700_000_000).is_ok());
+
+		let mut forged = delegation.clone();
+		forged.delegate_address = "ckt1qsomeoneelse".into();
+		assert!(forged.verify(1_700_000_000).is_err());
+
+		let mut other_network = delegation.clone();
+		other_network.network = "mainnet".into();
+		assert!(other_network.verify(1_700_000_000).is_err());
+
+		let mut old_format = delegation.clone();
+		old_format.version = 1;
+		assert!(old_format.verify(1_700_000_000).is_err());
+
+		let mut other_creator = delegation.clone();
+		other_creator.creator_address = signed(8, None).creator_address;
+		assert!(other_creator.verify(1_700_000_000).is_err());
+
+		// A creator whose signature cannot be checked offline is not trusted.
+		let payload = ckb_sdk::AddressPayload::new_full(
+			ckb_types::core::ScriptHashType::Type,
+			ckb_types::prelude::Pack::pack(&ckb_types::H256([7; 32])),
+			ckb_types::bytes::Bytes::from(vec![1; 20]),
+		);
+		let mut unchecked = delegation;
+		unchecked.creator_address =
+			ckb_sdk::Address::new(ckb_sdk::NetworkType::Testnet, payload, true).to_string();
+		let err = unchecked.verify(1_700_000_000).unwrap_err();
+		assert!(err.to_string().contains("unsupported lock"), "{err}");
+	}
+
+	#[test]
+	fn delegations_only_cover_their_event_and_creator() {
+		let delegation = signed(7, None);
+		let (network, event_id) = ("testnet", "ab".repeat(32));
+		let creator = delegation.creator_address.clone();
+		let check = |event_id: &str, creator: &str, delegate: &str| {
+			delegation.check(network, event_id, creator, delegate, 1_700_000_000)
+		};
+		assert!(check(&event_id, &creator, "ckt1qdelegate").is_ok());
+		assert!(check(&"cd".repeat(32), &creator, "ckt1qdelegate").is_err());
+		assert!(check(&event_id, "ckt1qsquatter", "ckt1qdelegate").is_err());
+		assert!(check(&event_id, &creator, "ckt1qsomeoneelse").is_err());
+		assert!(delegation
+			.check("mainnet", &event_id, &creator, "ckt1qdelegate", 1_700_000_000)
+			.is_err());
+	}
+
+	#[test]
+	fn expired_delegations_are_refused() {
+		let delegation = signed(7, Some(1_700_000_000));
+		assert!(delegation.verify(1_699_999_999).is_ok());
+		assert!(delegation.verify(1_700_000_000).is_err());
+		assert!(delegation.message().ends_with("|1700000000"));
+	}
+}
@@ -0,0 +1,537 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::crypto;
+
+/// Event details shown on ckb-pop.xyz and carried in event descriptors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventMetadata {
+	pub name: String,
+	#[serde(default)]
+	pub description: String,
+	pub image_url: Option<String>,
+	pub location: Option<String>,
+	pub start_time: Option<String>,
+	pub end_time: Option<String>,
+}
+
+/// Longest accepted event name, in characters.
+const MAX_NAME_LEN: usize = 120;
+/// Longest accepted description, in characters.
+const MAX_DESCRIPTION_LEN: usize = 2000;
+/// Longest accepted location, in characters.
+const MAX_LOCATION_LEN: usize = 200;
+/// Longest accepted image URL, in bytes.
+const MAX_URL_LEN: usize = 2048;
+
+/// Time formats accepted for `--start`/`--end` besides RFC 3339.  All carry
+/// an explicit offset so an event never silently takes the local timezone.
+const TIME_FORMATS: &[&str] = &[
+	"%Y-%m-%dT%H:%M:%S%#z",
+	"%Y-%m-%dT%H:%M%#z",
+	"%Y-%m-%d %H:%M:%S%#z",
+	"%Y-%m-%d %H:%M%#z",
+	"%Y-%m-%d %H:%M:%S %#z",
+	"%Y-%m-%d %H:%M %#z",
+];
+
+impl EventMetadata {
+	/// Validate user input and normalize it: names and locations are
+	/// trimmed, times converted to UTC RFC 3339, and the image URL checked.
+	pub fn from_input(
+		name: &str,
+		description: &str,
+		image_url: Option<&str>,
+		location: Option<&str>,
+		start: Option<&str>,
+		end: Option<&str>,
+	) -> Result<Self> {
+		let name = name.trim();
+		if name.is_empty() {
+			anyhow::bail!("--name must not be empty");
+		}
+		check_len("--name", name, MAX_NAME_LEN)?;
+		let description = description.trim();
+		check_len("--description", description, MAX_DESCRIPTION_LEN)?;
+		let location = location.map(str::trim).filter(|l| !l.is_empty());
+		if let Some(l) = location {
+			check_len("--location", l, MAX_LOCATION_LEN)?;
+		}
+		let image_url = image_url.map(str::trim).filter(|u| !u.is_empty());
+		if let Some(url) = image_url {
+			check_image_url(url)?;
+		}
+
+		let start = start.map(|s| parse_time("--start", s)).transpose()?;
+		let end = end.map(|e| parse_time("--end", e)).transpose()?;
+		if let (Some(s), Some(e)) = (start, end) {
+			if e <= s {
+				anyhow::bail!("--end ({}) must be after --start ({})", rfc3339(e), rfc3339(s));
+			}
+		}
+
+		Ok(Self {
+			name: name.to_owned(),
+			description: description.to_owned(),
+			image_url: image_url.map(str::to_owned),
+			location: location.map(str::to_owned),
+			start_time: start.map(rfc3339),
+			end_time: end.map(rfc3339),
+		})
+	}
+
+	/// Directory of content-addressed metadata files (~/.ckb-pop/metadata/).
+	pub fn dir() -> PathBuf {
+		Config::dir().join("metadata")
+	}
+
+	/// The document an anchor's `metadata_hash` commits to: every field
+	/// under its own name, with unset optional fields left out.
+	pub fn canonical_document(&self) -> Value {
+		let mut doc = serde_json::json!({
+			"name": self.name,
+			"description": self.description,
+		});
+		for (key, value) in [
+			("image_url", &self.image_url),
+			("location", &self.location),
+			("start_time", &self.start_time),
+			("end_time", &self.end_time),
+		] {
+			if let Some(v) = value {
+				doc[key] = v.as_str().into();
+			}
+		}
+		doc
+	}
+
+	/// [`crypto::canonical_hash`] of [`Self::canonical_document`], hex
+	/// encoded.  This is the `metadata_hash` of events created without the
+	/// backend; see [`Self::anchor_hash`].
+	pub fn hash(&self) -> String {
+		hex::encode(
+			crypto::canonical_hash(&self.canonical_document())
+				.expect("metadata holds only strings"),
+		)
+	}
+
+	/// The `metadata_hash` to anchor.  Events registered with the backend
+	/// keep [`Self::legacy_website_hash`], which ckb-pop.xyz recomputes to
+	/// verify them, until the website adopts the canonical encoding.
+	pub fn anchor_hash(&self, registered: bool) -> String {
+		if registered {
+			self.legacy_website_hash()
+		} else {
+			self.hash()
+		}
+	}
+
+	/// SHA-256 of the 4-key JSON (`name`, `date`, `location`,
+	/// `description`, with `date` the start time) that ckb-pop.xyz hashes
+	/// to verify backend-registered events.  The JSON is rendered with
+	/// [`crypto::canonical_json`], whose sorted keys match the website.
+	pub fn legacy_website_hash(&self) -> String {
+		let json = serde_json::json!({
+			"name": self.name,
+			"date": self.start_time,
+			"location": self.location,
+			"description": self.description,
+		});
+		let json = crypto::canonical_json(&json).expect("metadata holds only strings");
+		crypto::sha256_hex(json.as_bytes())
+	}
+
+	/// Which anchored form, if any, hashes to `metadata_hash`.
+	pub fn matches(&self, metadata_hash: &str) -> Option<MetadataFormat> {
+		let expected = metadata_hash.trim_start_matches("0x").to_ascii_lowercase();
+		[
+			(MetadataFormat::Canonical, self.hash()),
+			(MetadataFormat::LegacyWebsite, self.legacy_website_hash()),
+		]
+		.into_iter()
+		.find(|(_, hash)| *hash == expected)
+		.map(|(format, _)| format)
+	}
+
+	/// Read metadata from JSON that is either the metadata itself or wraps
+	/// it in a `metadata` field, as event descriptors and backend records do.
+	pub fn from_json(value: Value) -> Result<Self> {
+		let value = match value {
+			Value::Object(mut obj) if obj.get("metadata").is_some_and(Value::is_object) => {
+				obj.remove("metadata").unwrap_or_default()
+			}
+			other => other,
+		};
+		serde_json::from_value(value).context("JSON does not contain event metadata")
+	}
+
+	/// Write the canonical JSON to `<hash>.json` in [`Self::dir`] and return
+	/// the path.  Anyone holding the file can check it against the anchor.
+	pub fn store(&self) -> Result<PathBuf> {
+		let dir = Self::dir();
+		std::fs::create_dir_all(&dir)?;
+		let path = dir.join(format!("{}.json", self.hash()));
+		std::fs::write(&path, crypto::canonical_json(&self.canonical_document())?)?;
+		Ok(path)
+	}
+}
+
+fn check_len(flag: &str, value: &str, max: usize) -> Result<()> {
+	let len = value.chars().count();
+	if len > max {
+		anyhow::bail!("{flag} is {len} characters long; the limit is {max}");
+	}
+	Ok(())
+}
+
+fn check_image_url(url: &str) -> Result<()> {
+	if url.len() > MAX_URL_LEN {
+		anyhow::bail!("--image-url is longer than {MAX_URL_LEN} bytes");
+	}
+	let parsed = reqwest::Url::parse(url)
+		.map_err(|e| anyhow::anyhow!("--image-url {url:?} is not a valid URL: {e}"))?;
+	if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+		anyhow::bail!("--image-url must be an http(s) URL, got {url:?}");
+	}
+	Ok(())
+}
+
+/// Parse an event time given with an explicit offset.  `flag` names the
+/// input in the error message.
+pub fn parse_time(flag: &str, value: &str) -> Result<DateTime<Utc>> {
+	let value = value.trim();
+	DateTime::parse_from_rfc3339(value)
+		.ok()
+		.or_else(|| {
+			TIME_FORMATS
+				.iter()
+				.find_map(|f| DateTime::parse_from_str(value, f).ok())
+		})
+		.map(|t| t.with_timezone(&Utc))
+		.ok_or_else(|| {
+			anyhow::anyhow!(
+				"{flag} {value:?} is not a date and time with a timezone; \
+				 use e.g. 2026-06-01T10:00:00Z or \"2026-06-01 10:00 +02:00\""
+			)
+		})
+}
+
+/// Format a time as UTC RFC 3339 with whole seconds, e.g. `2026-06-01T08:00:00Z`.
+pub fn rfc3339(time: DateTime<Utc>) -> String {
+	time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// The creator an event-anchor cell names, if its type args commit to
+/// `event_id` and that creator as [`crypto::build_type_script_args`] lays
+/// them out.  A cell whose data and args disagree is not an anchor of the
+/// event.
+pub fn anchor_creator(event_id: &str, args: &[u8], data: &Value) -> Option<String> {
+	let creator = data.get("creator_address")?.as_str()?;
+	(args == crypto::build_type_script_args(event_id, creator).as_slice()).then(|| creator.to_owned())
+}
+
+/// The ways an anchor's `metadata_hash` has been derived.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetadataFormat {
+	/// [`EventMetadata::hash`], used for every event created now.
+	Canonical,
+	/// [`EventMetadata::legacy_website_hash`].
+	LegacyWebsite,
+}
+
+/// Everything needed to find and check an event created without the
+/// backend: where it is anchored and the metadata its anchor commits to.
+/// Shared with attendees and verifiers in place of a ckb-pop.xyz link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventDescriptor {
+	/// Descriptor format version.
+	pub version: u8,
+	/// Network the anchor lives on.
+	pub network: String,
+	pub event_id: String,
+	pub creator_address: String,
+	/// Hash of the anchor transaction.
+	pub anchor_tx_hash: String,
+	/// SHA-256 of the metadata, as stored in the anchor cell.
+	pub metadata_hash: String,
+	pub metadata: EventMetadata,
+}
+
+impl EventDescriptor {
+	pub const VERSION: u8 = 1;
+
+	/// Directory where descriptors are stored (~/.ckb-pop/events/).
+	pub fn dir() -> PathBuf {
+		Config::dir().join("events")
+	}
+
+	/// Write the descriptor to `<event_id>.json` and return its path.
+	pub fn save(&self) -> Result<PathBuf> {
+		let dir = Self::dir();
+		std::fs::create_dir_all(&dir)?;
+		let path = dir.join(format!("{}.json", self.event_id));
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(path)
+	}
+
+	/// The saved descriptor of `event_id` on `network`, if there is one.
+	pub fn load(network: &str, event_id: &str) -> Result<Option<Self>> {
+		if !crypto::is_event_id(event_id) {
+			return Ok(None);
+		}
+		let path = Self::dir().join(format!("{event_id}.json"));
+		if !path.exists() {
+			return Ok(None);
+		}
+		let content = std::fs::read_to_string(&path)?;
+		let descriptor: Self = serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid event descriptor", path.display()))?;
+		Ok((descriptor.network == network && descriptor.event_id == event_id).then_some(descriptor))
+	}
+}
+
+/// An event registered with the backend whose anchor the backend has not
+/// yet recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingActivation {
+	pub network: String,
+	pub event_id: String,
+	/// Hash of the signed anchor transaction; `None` until the wallet has
+	/// signed it.
+	#[serde(default)]
+	pub anchor_tx_hash: Option<String>,
+	pub creator_address: String,
+	/// [`EventMetadata::hash`] of the details the event was created with,
+	/// so a re-run of `event create` recognises the same event.
+	pub metadata_hash: String,
+	/// Unix timestamp at which the backend assigned the event ID.
+	pub created_at: i64,
+}
+
+/// Activations still owed to the backend, kept across runs so that
+/// `event activate` and a re-run of `event create` can finish them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingActivations {
+	#[serde(default)]
+	pub events: Vec<PendingActivation>,
+}
+
+impl PendingActivations {
+	/// Path to the pending activation list.
+	pub fn path() -> PathBuf {
+		Config::dir().join("pending_activations.json")
+	}
+
+	/// Load the list, which is empty if the file does not exist yet.
+	pub fn load() -> Result<Self> {
+		let path = Self::path();
+		if !path.exists() {
+			return Ok(Self::default());
+		}
+		let content = std::fs::read_to_string(&path)?;
+		serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid activation list", path.display()))
+	}
+
+	pub fn save(&self) -> Result<()> {
+		let path = Self::path();
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+
+	pub fn find(&self, network: &str, event_id: &str) -> Option<&PendingActivation> {
+		self.events
+			.iter()
+			.find(|p| p.network == network && p.event_id == event_id)
+	}
+
+	/// The pending event `creator` created on `network` with the metadata
+	/// hashing to `metadata_hash`, if any.
+	pub fn find_matching(
+		&self,
+		network: &str,
+		creator: &str,
+		metadata_hash: &str,
+	) -> Option<&PendingActivation> {
+		self.events.iter().find(|p| {
+			p.network == network && p.creator_address == creator && p.metadata_hash == metadata_hash
+		})
+	}
+
+	/// Add `pending`, replacing any entry for the same event.
+	pub fn insert(&mut self, pending: PendingActivation) {
+		self.remove(&pending.network, &pending.event_id);
+		self.events.push(pending);
+	}
+
+	/// Drop the entry for an event; returns whether there was one.
+	pub fn remove(&mut self, network: &str, event_id: &str) -> bool {
+		let before = self.events.len();
+		self.events
+			.retain(|p| !(p.network == network && p.event_id == event_id));
+		self.events.len() != before
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn metadata() -> EventMetadata {
+		EventMetadata {
+			name: "Meetup".into(),
+			description: "Monthly meetup".into(),
+			image_url: None,
+			location: Some("Berlin".into()),
+			start_time: Some("2026-11-01T18:00:00Z".into()),
+			end_time: None,
+		}
+	}
+
+	#[test]
+	fn metadata_hash_golden_vector() {
+		let m = metadata();
+		assert_eq!(
+			crypto::canonical_json(&m.canonical_document()).unwrap(),
+			r#"{"description":"Monthly meetup","location":"Berlin","name":"Meetup","start_time":"2026-11-01T18:00:00Z"}"#
+		);
+		assert_eq!(m.hash(), "17cc888cf6ee5ee1ade156369c2935717017f83f929090239abb29dca168f894");
+
+		let mut changed = m.clone();
+		changed.end_time = Some("2026-11-01T21:00:00Z".into());
+		assert_ne!(m.hash(), changed.hash());
+	}
+
+	#[test]
+	fn anchor_data_must_match_its_args() {
+		let event_id = "ab".repeat(32);
+		let data = serde_json::json!({ "creator_address": "ckt1qcreator" });
+		let args = crypto::build_type_script_args(&event_id, "ckt1qcreator");
+		assert_eq!(anchor_creator(&event_id, &args, &data).as_deref(), Some("ckt1qcreator"));
+		// Data claiming a creator the args do not commit to is not an anchor.
+		let other = crypto::build_type_script_args(&event_id, "ckt1qsquatter");
+		assert_eq!(anchor_creator(&event_id, &other, &data), None);
+		assert_eq!(anchor_creator(&event_id, &args, &Value::Null), None);
+	}
+
+	#[test]
+	fn descriptor_roundtrip() {
+		let metadata = metadata();
+		let descriptor = EventDescriptor {
+			version: EventDescriptor::VERSION,
+			network: "testnet".into(),
+			event_id: "ab".repeat(32),
+			creator_address: "ckt1qcreator".into(),
+			anchor_tx_hash: "0x1234".into(),
+			metadata_hash: metadata.hash(),
+			metadata,
+		};
+		let json = serde_json::to_string(&descriptor).unwrap();
+		let parsed: EventDescriptor = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, descriptor);
+	}
+
+	#[test]
+	fn pending_activations_are_keyed_by_network_and_event() {
+		let pending = |event_id: &str, tx: &str| PendingActivation {
+			network: "testnet".into(),
+			event_id: event_id.into(),
+			anchor_tx_hash: Some(tx.into()),
+			creator_address: "ckt1qcreator".into(),
+			metadata_hash: metadata().hash(),
+			created_at: 1_700_000_000,
+		};
+
+		let mut list = PendingActivations::default();
+		list.insert(pending("evt", "0x01"));
+		list.insert(pending("evt", "0x02"));
+		assert_eq!(list.events.len(), 1);
+		let found = list.find("testnet", "evt").unwrap();
+		assert_eq!(found.anchor_tx_hash.as_deref(), Some("0x02"));
+		assert!(list.find("mainnet", "evt").is_none());
+
+		let found = list.find_matching("testnet", "ckt1qcreator", &metadata().hash());
+		assert_eq!(found.map(|p| p.event_id.as_str()), Some("evt"));
+		assert!(list.find_matching("testnet", "ckt1qother", &metadata().hash()).is_none());
+		// A field the anchor does not commit to still tells events apart.
+		let mut other = metadata();
+		other.image_url = Some("https://example.test/other.png".into());
+		assert_eq!(other.anchor_hash(true), metadata().anchor_hash(true));
+		assert!(list.find_matching("testnet", "ckt1qcreator", &other.hash()).is_none());
+
+		assert!(list.remove("testnet", "evt"));
+		assert!(!list.remove("testnet", "evt"));
+	}
+
+	#[test]
+	fn matches_canonical_and_legacy_hashes() {
+		let m = metadata();
+		let website = crypto::sha256_hex(
+			br#"{"date":"2026-11-01T18:00:00Z","description":"Monthly meetup","location":"Berlin","name":"Meetup"}"#,
+		);
+		assert_eq!(m.legacy_website_hash(), website);
+		// Registered events anchor the hash the website recomputes.
+		assert_eq!(m.anchor_hash(true), website);
+		assert_eq!(m.anchor_hash(false), m.hash());
+		assert_eq!(m.matches(&m.hash()), Some(MetadataFormat::Canonical));
+		assert_eq!(m.matches(&format!("0x{website}")), Some(MetadataFormat::LegacyWebsite));
+		assert_eq!(m.matches(&"00".repeat(32)), None);
+	}
+
+	#[test]
+	fn metadata_is_read_bare_or_wrapped() {
+		let m = metadata();
+		let bare = serde_json::to_value(&m).unwrap();
+		let wrapped = serde_json::json!({ "event_id": "evt", "metadata": bare.clone() });
+		assert_eq!(EventMetadata::from_json(bare).unwrap(), m);
+		assert_eq!(EventMetadata::from_json(wrapped).unwrap(), m);
+		assert!(EventMetadata::from_json(serde_json::json!({ "event_id": "evt" })).is_err());
+	}
+
+	#[test]
+	fn input_times_are_normalized_to_utc() {
+		let m = EventMetadata::from_input(
+			"  Meetup ",
+			"",
+			Some("https://example.com/badge.png"),
+			Some(" "),
+			Some("2026-06-01 10:00 +02:00"),
+			Some("2026-06-01T12:30:00+02:00"),
+		)
+		.unwrap();
+		assert_eq!(m.name, "Meetup");
+		assert_eq!(m.location, None);
+		assert_eq!(m.start_time.as_deref(), Some("2026-06-01T08:00:00Z"));
+		assert_eq!(m.end_time.as_deref(), Some("2026-06-01T10:30:00Z"));
+
+		for (start, end) in [
+			("2026-06-01T10:00:00Z", "2026-06-01T09:00:00Z"),
+			("2026-06-01T10:00:00Z", "2026-06-01T10:00:00Z"),
+		] {
+			assert!(EventMetadata::from_input("m", "", None, None, Some(start), Some(end)).is_err());
+		}
+	}
+
+	#[test]
+	fn invalid_input_is_rejected() {
+		let create = |name: &str, image: Option<&str>, start: Option<&str>| {
+			EventMetadata::from_input(name, "", image, None, start, None)
+		};
+		assert!(create("", None, None).is_err());
+		assert!(create(&"x".repeat(MAX_NAME_LEN + 1), None, None).is_err());
+		assert!(create("m", Some("ftp://example.com/a.png"), None).is_err());
+		assert!(create("m", Some("not a url"), None).is_err());
+		// Times without a timezone or outside the calendar are refused.
+		assert!(create("m", None, Some("2026-06-01 10:00")).is_err());
+		assert!(create("m", None, Some("2026-02-30T10:00:00Z")).is_err());
+		assert!(create("m", None, Some("2026-06-01T10:00Z")).is_ok());
+	}
+}
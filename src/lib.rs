@@ -1,8 +1,20 @@
+pub mod alias;
+pub mod backend;
 pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod contracts;
+pub mod credential;
 pub mod crypto;
+pub mod delegation;
+pub mod event;
+pub mod manifest;
+#[cfg(test)]
+mod mock;
+pub mod preflight;
+pub mod proof;
+pub mod queue;
 pub mod rpc;
+pub mod schedule;
 pub mod signer;
 pub mod tx_builder;
@@ -1,12 +1,24 @@
 use anyhow::Result;
 use clap::Parser;
 
+mod alias;
+mod backend;
 mod cli;
 mod commands;
 mod config;
 mod contracts;
+mod credential;
 mod crypto;
+mod delegation;
+mod event;
+mod manifest;
+#[cfg(test)]
+mod mock;
+mod preflight;
+mod proof;
+mod queue;
 mod rpc;
+mod schedule;
 mod signer;
 
 mod tx_builder;
@@ -20,8 +32,18 @@ async fn main() -> Result<()> {
 	match &cli.command {
 		Command::Signer { command } => commands::signer::run(command).await,
 		Command::Event { command } => commands::event::run(&cli, command).await,
-		Command::Attend { qr_data } => commands::attend::run(&cli, qr_data).await,
+		Command::Attend {
+			qr_data,
+			wait,
+			dry_run,
+			flush,
+		} => match qr_data {
+			Some(qr_data) if !*flush => commands::attend::run(&cli, qr_data, *wait, *dry_run).await,
+			_ => commands::attend::flush(&cli, *wait).await,
+		},
 		Command::Badge { command } => commands::badge::run(&cli, command).await,
+		Command::Proof { command } => commands::proof::run(&cli, command).await,
 		Command::Tx { command } => commands::tx::run(&cli, command).await,
+		Command::Contracts { command } => commands::contracts::run(&cli, command).await,
 	}
 }
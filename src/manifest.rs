@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::event::EventMetadata;
+use crate::schedule::{self, Window, WindowSpec};
+
+/// An event described in a file, for `event create --from`.
+///
+/// The same fields can be written as TOML or JSON; the format is
+/// picked from the file extension.
+///
+/// ```toml
+/// name = "CKB Community Meetup"
+/// location = "Berlin"
+/// start = "2026-06-01 18:00 +02:00"
+/// end = "2026-06-01 21:00 +02:00"
+/// co_organizers = ["ckt1q..."]
+///
+/// [[windows]]
+/// start = "2026-06-01 18:00 +02:00"
+/// end = "2026-06-01 18:30 +02:00"
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventManifest {
+	pub name: String,
+	#[serde(default)]
+	pub description: String,
+	pub image_url: Option<String>,
+	pub location: Option<String>,
+	pub start: Option<String>,
+	pub end: Option<String>,
+	/// Planned attendance windows.
+	#[serde(default)]
+	pub windows: Vec<WindowSpec>,
+	/// Addresses allowed to run the event alongside its creator.
+	#[serde(default)]
+	pub co_organizers: Vec<String>,
+}
+
+/// File formats a manifest or schedule can be written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+	Toml,
+	Json,
+}
+
+impl Format {
+	pub(crate) fn of(path: &Path) -> Option<Self> {
+		let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+		Some(match ext.as_str() {
+			"toml" => Self::Toml,
+			"json" => Self::Json,
+			_ => return None,
+		})
+	}
+
+	pub(crate) fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+		Ok(match self {
+			Self::Toml => toml::from_str(content)?,
+			Self::Json => serde_json::from_str(content)?,
+		})
+	}
+}
+
+/// Read a TOML or JSON file, picking the format from its extension.
+/// `what` names the document in errors.
+pub(crate) fn read_document<T: DeserializeOwned>(path: &Path, what: &str) -> Result<T> {
+	let content = std::fs::read_to_string(path)
+		.with_context(|| format!("could not read {}", path.display()))?;
+	let format = Format::of(path).ok_or_else(|| {
+		anyhow::anyhow!(
+			"{}: unknown {what} format; use a .toml or .json file",
+			path.display()
+		)
+	})?;
+	format
+		.parse(&content)
+		.with_context(|| format!("{} is not a valid {what}", path.display()))
+}
+
+impl EventManifest {
+	/// Whether `path` names a manifest file rather than, say, an event ID.
+	pub fn is_manifest_path(path: &Path) -> bool {
+		Format::of(path).is_some() && path.is_file()
+	}
+
+	/// Read and validate a manifest.
+	pub fn load(path: &Path) -> Result<Self> {
+		let manifest: Self = read_document(path, "event manifest")?;
+		manifest
+			.validate()
+			.with_context(|| format!("{} is not a valid event manifest", path.display()))?;
+		Ok(manifest)
+	}
+
+	fn validate(&self) -> Result<()> {
+		self.metadata()?;
+		self.windows()?;
+		self.co_organizers()?;
+		Ok(())
+	}
+
+	/// The event metadata, validated and normalized like `event create` flags.
+	pub fn metadata(&self) -> Result<EventMetadata> {
+		EventMetadata::from_input(
+			&self.name,
+			&self.description,
+			self.image_url.as_deref(),
+			self.location.as_deref(),
+			self.start.as_deref(),
+			self.end.as_deref(),
+		)
+	}
+
+	/// The attendance windows in order; see [`schedule::parse_windows`].
+	pub fn windows(&self) -> Result<Vec<Window>> {
+		schedule::parse_windows(&self.windows)
+	}
+
+	/// The co-organizer addresses, checked to be valid CKB addresses.
+	pub fn co_organizers(&self) -> Result<Vec<String>> {
+		self.co_organizers
+			.iter()
+			.map(|a| {
+				let a = a.trim();
+				a.parse::<ckb_sdk::Address>()
+					.map_err(|e| anyhow::anyhow!("co_organizers: invalid CKB address {a:?}: {e}"))?;
+				Ok(a.to_owned())
+			})
+			.collect()
+	}
+}
+
+/// An event created from a manifest on one network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEvent {
+	pub event_id: String,
+	pub anchor_tx_hash: String,
+	/// Unix timestamp at which the anchor was broadcast.
+	pub created_at: i64,
+}
+
+/// Events created from a manifest, keyed by network.  Stored next to the
+/// manifest so later commands can take the manifest path in place of the
+/// event ID.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestState {
+	#[serde(flatten)]
+	pub networks: BTreeMap<String, ManifestEvent>,
+}
+
+impl ManifestState {
+	/// State file for `manifest`: `event.toml` keeps its state in
+	/// `event.ckb-pop.json` in the same directory.
+	pub fn path(manifest: &Path) -> PathBuf {
+		let stem = manifest
+			.file_stem()
+			.map(|s| s.to_string_lossy().into_owned())
+			.unwrap_or_default();
+		manifest.with_file_name(format!("{stem}.ckb-pop.json"))
+	}
+
+	/// Load the state for `manifest`, which is empty if nothing has been
+	/// created from it yet.
+	pub fn load(manifest: &Path) -> Result<Self> {
+		let path = Self::path(manifest);
+		if !path.exists() {
+			return Ok(Self::default());
+		}
+		let content = std::fs::read_to_string(&path)?;
+		serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid manifest state file", path.display()))
+	}
+
+	pub fn save(&self, manifest: &Path) -> Result<PathBuf> {
+		let path = Self::path(manifest);
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(path)
+	}
+
+	pub fn get(&self, network: &str) -> Option<&ManifestEvent> {
+		self.networks.get(network)
+	}
+
+	/// Record the event created on `network` and write the state file.
+	pub fn record(manifest: &Path, network: &str, event: ManifestEvent) -> Result<PathBuf> {
+		let mut state = Self::load(manifest)?;
+		state.networks.insert(network.to_owned(), event);
+		state.save(manifest)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TOML: &str = r#"
+name = "Meetup"
+description = "Monthly meetup"
+location = "Berlin"
+start = "2026-11-01 19:00 +01:00"
+
+[[windows]]
+start = "2026-11-01T19:30:00+01:00"
+end = "2026-11-01T20:00:00+01:00"
+
+[[windows]]
+start = "2026-11-01T18:00:00Z"
+end = "2026-11-01T18:15:00Z"
+"#;
+
+	const JSON: &str = r#"{
+  "name": "Meetup",
+  "description": "Monthly meetup",
+  "location": "Berlin",
+  "start": "2026-11-01 19:00 +01:00",
+  "windows": [
+    { "start": "2026-11-01T19:30:00+01:00", "end": "2026-11-01T20:00:00+01:00" },
+    { "start": "2026-11-01T18:00:00Z", "end": "2026-11-01T18:15:00Z" }
+  ]
+}"#;
+
+	fn parse(content: &str, format: Format) -> Result<EventManifest> {
+		let manifest: EventManifest = format.parse(content)?;
+		manifest.validate()?;
+		Ok(manifest)
+	}
+
+	#[test]
+	fn formats_describe_the_same_event() {
+		let toml = parse(TOML, Format::Toml).unwrap();
+		let json = parse(JSON, Format::Json).unwrap();
+		assert_eq!(toml, json);
+
+		let metadata = toml.metadata().unwrap();
+		assert_eq!(metadata.start_time.as_deref(), Some("2026-11-01T18:00:00Z"));
+		assert_eq!(metadata.hash(), "17cc888cf6ee5ee1ade156369c2935717017f83f929090239abb29dca168f894");
+
+		let windows = toml.windows().unwrap();
+		assert_eq!(crate::event::rfc3339(windows[0].start), "2026-11-01T18:00:00Z");
+		assert_eq!(crate::event::rfc3339(windows[1].end), "2026-11-01T19:00:00Z");
+		assert_eq!(Format::of(Path::new("event.JSON")), Some(Format::Json));
+		assert_eq!(Format::of(Path::new("event.yaml")), None);
+		assert_eq!(Format::of(Path::new("0xabc")), None);
+	}
+
+	#[test]
+	fn invalid_manifests_are_rejected() {
+		let with = |extra: &str| parse(&format!("name = \"m\"\n{extra}"), Format::Toml);
+		assert!(with("").is_ok());
+		assert!(with("unknown = 1").is_err());
+		assert!(with("co_organizers = [\"not-an-address\"]").is_err());
+		assert!(with("[[windows]]\nstart = \"2026-11-01T18:00:00Z\"\nend = \"2026-11-01T18:00:00Z\"").is_err());
+		assert!(with(
+			"[[windows]]\nstart = \"2026-11-01T18:00:00Z\"\nend = \"2026-11-01T19:00:00Z\"\n\
+			 [[windows]]\nstart = \"2026-11-01T18:30:00Z\"\nend = \"2026-11-01T20:00:00Z\""
+		)
+		.is_err());
+		assert!(parse("name = \"\"", Format::Toml).is_err());
+	}
+
+	#[test]
+	fn state_is_stored_next_to_the_manifest() {
+		assert_eq!(
+			ManifestState::path(Path::new("events/meetup.toml")),
+			Path::new("events/meetup.ckb-pop.json")
+		);
+
+		let mut state = ManifestState::default();
+		state.networks.insert(
+			"testnet".into(),
+			ManifestEvent {
+				event_id: "ab".repeat(32),
+				anchor_tx_hash: "0x01".into(),
+				created_at: 1_700_000_000,
+			},
+		);
+		let json = serde_json::to_string(&state).unwrap();
+		assert!(json.starts_with(r#"{"testnet":{"#), "{json}");
+		let parsed: ManifestState = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, state);
+		assert!(parsed.get("mainnet").is_none());
+	}
+}
@@ -0,0 +1,44 @@
+//! Localhost HTTP servers with canned JSON responses, shared by the
+//! backend and RPC client tests.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Answer every request with the same status line and body.  Returns the
+/// server's base URL.
+pub async fn respond_with(status: &'static str, body: &'static str) -> String {
+	serve(move |_| (status, body)).await
+}
+
+/// Answer JSON-RPC requests with the body routed to their method, or an
+/// empty object for methods without a route.
+pub async fn json_rpc(routes: &'static [(&'static str, &'static str)]) -> String {
+	serve(move |req| {
+		let body = routes
+			.iter()
+			.find(|(method, _)| req.contains(&format!("\"method\":\"{method}\"")))
+			.map_or("{}", |(_, body)| body);
+		("200 OK", body)
+	})
+	.await
+}
+
+async fn serve<F>(respond: F) -> String
+where
+	F: Fn(&str) -> (&'static str, &'static str) + Send + 'static,
+{
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	tokio::spawn(async move {
+		while let Ok((mut stream, _)) = listener.accept().await {
+			let mut buf = vec![0u8; 8192];
+			let n = stream.read(&mut buf).await.unwrap_or(0);
+			let (status, body) = respond(&String::from_utf8_lossy(&buf[..n]));
+			let resp = format!(
+				"HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+				body.len()
+			);
+			let _ = stream.write_all(resp.as_bytes()).await;
+		}
+	});
+	url
+}
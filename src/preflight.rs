@@ -0,0 +1,287 @@
+use anyhow::Result;
+use ckb_jsonrpc_types as json;
+use ckb_types::{
+	bytes::Bytes,
+	core::{Capacity, DepType, ScriptHashType, TransactionView},
+	packed::{CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+	prelude::*,
+	H256,
+};
+
+use crate::contracts::NetworkContracts;
+use crate::rpc::{RpcClient, RpcError, SearchKey};
+
+/// Fee reserved in the dry-run copy of a transaction.  Far above the
+/// minimum fee rate for anything this CLI builds.
+const PREFLIGHT_FEE: u64 = 100_000;
+
+/// Length of a secp256k1 recoverable signature in a sighash witness.
+const SIGNATURE_LEN: usize = 65;
+
+/// secp256k1/blake160 dep group out points from the genesis blocks.
+const SIGHASH_DEP_TESTNET: &str =
+	"f8de3bb47d055cdf460d93a2a6e1b05f7432f9777c8c474abf4eec1d4aee5d37";
+const SIGHASH_DEP_MAINNET: &str =
+	"71a7ba8fc96349fea0ed3a5c47992e3b4084b031a42264a018e0072e8172e46c";
+
+/// Result of dry-running a transaction against the node.
+///
+/// The dry-run carries a placeholder signature, and the node runs lock
+/// scripts before type scripts, so the dob-badge and event-anchor type
+/// scripts normally never execute here.  What it catches is everything
+/// checked before them: missing or spent cells and deps, capacity, fee
+/// rate, and transaction shape.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+	/// The node accepted the transaction outright.
+	Accepted { cycles: u64, fee: u64 },
+	/// The transaction resolves and is well-formed; verification stopped
+	/// at the payer's lock because the witness is only a placeholder.
+	/// The PoP type scripts first run once the wallet has signed.
+	NeedsSignature,
+	/// The node rejected the transaction for a reason signing won't fix.
+	Rejected(String),
+}
+
+/// Balance a copy of `tx` from the payer's capacity cells, attach
+/// placeholder witnesses, and ask the node whether it would accept it.
+///
+/// The original `tx` is left untouched: the wallet still does its own
+/// balancing when it signs.
+pub async fn check(
+	rpc: &RpcClient,
+	network: &str,
+	contracts: &NetworkContracts,
+	tx: &TransactionView,
+	payer: &Script,
+) -> Result<Verdict> {
+	let tx = match balance(rpc, network, tx, payer).await? {
+		Ok(tx) => tx,
+		Err(reason) => return Ok(Verdict::Rejected(reason)),
+	};
+
+	let json_tx = json::TransactionView::from(tx.clone()).inner;
+	match rpc.test_tx_pool_accept(json_tx).await {
+		Ok(entry) => Ok(Verdict::Accepted {
+			cycles: entry.cycles.into(),
+			fee: entry.fee.into(),
+		}),
+		Err(err) => match err.downcast_ref::<RpcError>() {
+			Some(RpcError::Node { message, .. }) => Ok(explain(message, &tx, contracts)),
+			_ => Err(err),
+		},
+	}
+}
+
+/// Add inputs from the payer until the outputs and a change cell are
+/// covered.  Returns `Err(reason)` when the payer cannot afford it.
+async fn balance(
+	rpc: &RpcClient,
+	network: &str,
+	tx: &TransactionView,
+	payer: &Script,
+) -> Result<std::result::Result<TransactionView, String>> {
+	let change = CellOutput::new_builder().lock(payer.clone()).build();
+	let change_min: u64 = change.occupied_capacity(Capacity::zero())?.as_u64();
+	let outputs: u64 = tx.outputs_capacity()?.as_u64();
+	let required = outputs + PREFLIGHT_FEE + change_min;
+
+	let mut inputs = Vec::new();
+	let mut available = 0u64;
+	let mut cells = rpc.stream_cells(SearchKey::capacity_cells(payer.clone().into()));
+	while available < required {
+		let Some(cell) = cells.next().await? else {
+			return Ok(Err(format!(
+				"payer holds {available} shannons in spendable cells, needs at least {required}"
+			)));
+		};
+		available += u64::from(cell.output.capacity);
+		inputs.push(CellInput::new(cell.out_point.into(), 0));
+	}
+
+	let placeholder = WitnessArgs::new_builder()
+		.lock(Some(Bytes::from(vec![0u8; SIGNATURE_LEN])).pack())
+		.build();
+	let witnesses = std::iter::once(placeholder.as_bytes().pack())
+		.chain(std::iter::repeat(Bytes::new().pack()))
+		.take(inputs.len());
+
+	let change = change
+		.as_builder()
+		.capacity(Capacity::shannons(available - outputs - PREFLIGHT_FEE).pack())
+		.build();
+
+	let mut builder = tx
+		.as_advanced_builder()
+		.inputs(inputs)
+		.output(change)
+		.output_data(Bytes::new().pack())
+		.witnesses(witnesses);
+	if let Some(dep) = sighash_dep(network, payer) {
+		builder = builder.cell_dep(dep);
+	}
+	Ok(Ok(builder.build()))
+}
+
+/// The secp256k1 dep group, if `lock` is the default sighash lock.  Other
+/// locks fail to resolve without their deps, which still reads as a lock
+/// failure and so as [`Verdict::NeedsSignature`].
+fn sighash_dep(network: &str, lock: &Script) -> Option<CellDep> {
+	let is_sighash = lock.code_hash() == ckb_sdk::constants::SIGHASH_TYPE_HASH.pack()
+		&& lock.hash_type() == ScriptHashType::Type.into();
+	// Devnet genesis blocks differ; without a known dep the lock simply
+	// fails to resolve.
+	let tx_hash: H256 = match network {
+		"mainnet" => SIGHASH_DEP_MAINNET,
+		"testnet" => SIGHASH_DEP_TESTNET,
+		_ => return None,
+	}
+	.parse()
+	.ok()?;
+	is_sighash.then(|| {
+		CellDep::new_builder()
+			.out_point(OutPoint::new(tx_hash.pack(), 0))
+			.dep_type(DepType::DepGroup)
+			.build()
+	})
+}
+
+// -- Error translation --
+
+/// Which script group failed, as reported in a node verification error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScriptSource {
+	InputLock(usize),
+	InputType(usize),
+	OutputType(usize),
+}
+
+/// Turn a node rejection message into a verdict.
+fn explain(message: &str, tx: &TransactionView, contracts: &NetworkContracts) -> Verdict {
+	let Some((source, code)) = parse_script_error(message) else {
+		return Verdict::Rejected(describe_node_error(message));
+	};
+
+	let index = match source {
+		ScriptSource::InputLock(_) => return Verdict::NeedsSignature,
+		// Inputs are plain capacity cells; a failing input type script
+		// has no PoP meaning to translate.
+		ScriptSource::InputType(i) => {
+			return Verdict::Rejected(format!(
+				"type script of input {i} failed{}",
+				code.map(|c| format!(" with exit code {c}")).unwrap_or_default()
+			));
+		}
+		ScriptSource::OutputType(i) => i,
+	};
+
+	let code_hash = tx
+		.outputs()
+		.get(index)
+		.and_then(|o| o.type_().to_opt())
+		.map(|s| format!("0x{}", hex::encode(s.code_hash().raw_data())));
+	let name = match code_hash.as_deref() {
+		Some(h) if h == contracts.dob_badge.code_hash => "dob-badge",
+		Some(h) if h == contracts.event_anchor.code_hash => "event-anchor",
+		_ => "type",
+	};
+	let reason = match code {
+		Some(c) => format!("exit code {c}"),
+		None => describe_node_error(message),
+	};
+	Verdict::Rejected(format!("{name} script rejected output {index}: {reason}"))
+}
+
+/// Extract the failing group and exit code from a message such as
+/// `TransactionScriptError { source: Outputs[0].Type, cause:
+/// ValidationFailure: see error code 7 on page ... }`.
+fn parse_script_error(message: &str) -> Option<(ScriptSource, Option<i8>)> {
+	let (_, rest) = message.split_once("source: ")?;
+	let (kind, rest) = rest.split_once('[')?;
+	let (index, rest) = rest.split_once(']')?;
+	let index: usize = index.parse().ok()?;
+	let source = match (kind, rest.starts_with(".Lock")) {
+		("Inputs", true) => ScriptSource::InputLock(index),
+		("Inputs", false) => ScriptSource::InputType(index),
+		("Outputs", _) => ScriptSource::OutputType(index),
+		_ => return None,
+	};
+	let code = message.split_once("error code ").and_then(|(_, r)| {
+		let end = r
+			.char_indices()
+			.find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+			.map_or(r.len(), |(i, _)| i);
+		r[..end].parse().ok()
+	});
+	Some((source, code))
+}
+
+/// Readable summary for verification failures that are not script errors.
+fn describe_node_error(message: &str) -> String {
+	let known = [
+		("InsufficientCellCapacity", "an output holds less capacity than it occupies"),
+		("Dead(OutPoint", "an input or cell dep has already been spent"),
+		("Unknown(OutPoint", "an input or cell dep does not exist on this network"),
+		("PoolRejectedLowFeeRate", "the fee is below the node's minimum fee rate"),
+		("ExceededMaximumCycles", "scripts exceed the maximum cycle limit"),
+	];
+	known
+		.iter()
+		.find(|(needle, _)| message.contains(needle))
+		.map(|(_, text)| (*text).to_owned())
+		.unwrap_or_else(|| message.lines().next().unwrap_or(message).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::contracts::Contracts;
+
+	fn badge_tx() -> TransactionView {
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
+		crate::tx_builder::build_badge_mint(
+			&c.dob_badge,
+			"evt",
+			"ckt1qrecipient",
+			Script::default(),
+			"ckt1qissuer",
+			None,
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn lock_failure_means_signature_needed() {
+		let msg = "TransactionFailedToVerify: Verification failed Script(TransactionScriptError \
+			{ source: Inputs[0].Lock, cause: ValidationFailure: see error code -31 on page ... })";
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
+		assert_eq!(explain(msg, &badge_tx(), c), Verdict::NeedsSignature);
+	}
+
+	#[test]
+	fn type_failure_names_the_script() {
+		let msg = "TransactionFailedToVerify: Verification failed Script(TransactionScriptError \
+			{ source: Outputs[0].Type, cause: ValidationFailure: see error code 8 on page ... })";
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
+		assert_eq!(
+			explain(msg, &badge_tx(), c),
+			Verdict::Rejected("dob-badge script rejected output 0: exit code 8".into())
+		);
+	}
+
+	#[test]
+	fn parses_negative_codes_and_resolve_errors() {
+		let (source, code) =
+			parse_script_error("{ source: Inputs[2].Type, cause: see error code -1 on page }")
+				.unwrap();
+		assert_eq!(source, ScriptSource::InputType(2));
+		assert_eq!(code, Some(-1));
+
+		let msg = "TransactionFailedToResolve: Resolve failed Dead(OutPoint(0x01))";
+		assert!(parse_script_error(msg).is_none());
+		assert_eq!(describe_node_error(msg), "an input or cell dep has already been spent");
+	}
+}
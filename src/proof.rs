@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::crypto;
+
+/// A self-contained record of one attendance, written by `attend` so the
+/// attendee can later prove presence without relying on the backend.
+///
+/// Everything needed to re-check the proof is stored verbatim: the QR
+/// payload that was scanned, the exact message the wallet signed, the
+/// signature, and where the resulting badge landed on-chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofBundle {
+	/// Bundle format version.
+	pub version: u8,
+	/// Network the badge was minted on.
+	pub network: String,
+	/// Raw QR payload as scanned.
+	pub qr_data: String,
+	/// Attendee address that signed the message and holds the badge.
+	pub address: String,
+	/// Address recorded as the badge issuer.
+	pub issuer: String,
+	/// The attendance message presented to the wallet.
+	pub message: String,
+	/// Hex-encoded wallet signature over `message`.
+	pub signature: String,
+	/// Hash of the badge mint transaction.
+	pub badge_tx_hash: String,
+	/// Block the mint was committed in, if known when the bundle was saved.
+	pub block_hash: Option<String>,
+	/// Unix timestamp at which the attendee signed `message`, as recorded
+	/// by their CLI.  Self-reported: it is not covered by the signature.
+	/// Absent in bundles written before it was recorded.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub signed_at: Option<i64>,
+	/// Unix timestamp at which the bundle was created.
+	pub created_at: i64,
+}
+
+impl ProofBundle {
+	pub const VERSION: u8 = 1;
+
+	/// Directory where proof bundles are stored (~/.ckb-pop/proofs/).
+	pub fn dir() -> PathBuf {
+		Config::dir().join("proofs")
+	}
+
+	/// Write the bundle into the proofs directory and return its path.
+	/// File names are `<event_id prefix>-<tx hash prefix>.json`, so repeated
+	/// saves of the same mint overwrite rather than accumulate.  Both come
+	/// from outside (a scanned QR code, an RPC reply) and must be hex.
+	pub fn save(&self, event_id: &str) -> Result<PathBuf> {
+		let tx = self.badge_tx_hash.strip_prefix("0x").unwrap_or(&self.badge_tx_hash);
+		if !crypto::is_event_id(event_id) {
+			anyhow::bail!("{event_id:?} is not an event ID");
+		}
+		if tx.is_empty() || !tx.bytes().all(|b| b.is_ascii_hexdigit()) {
+			anyhow::bail!("{:?} is not a transaction hash", self.badge_tx_hash);
+		}
+
+		let dir = Self::dir();
+		std::fs::create_dir_all(&dir)?;
+		let name = format!("{}-{}.json", short(event_id), short(tx));
+		let path = dir.join(name);
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(path)
+	}
+
+	/// Find the saved bundle of the badge minted in `tx_hash`, if any.
+	pub fn find_by_tx(tx_hash: &str) -> Option<Self> {
+		std::fs::read_dir(Self::dir())
+			.ok()?
+			.flatten()
+			.filter_map(|entry| Self::load(&entry.path()).ok())
+			.find(|bundle| bundle.badge_tx_hash == tx_hash)
+	}
+
+	/// Read a bundle from any path.
+	pub fn load(path: &Path) -> Result<Self> {
+		let content = std::fs::read_to_string(path)
+			.with_context(|| format!("could not read {}", path.display()))?;
+		serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid proof bundle", path.display()))
+	}
+}
+
+/// First 16 characters of an ASCII hex string.
+fn short(hex: &str) -> &str {
+	&hex[..hex.len().min(16)]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn json_roundtrip() {
+		let bundle = ProofBundle {
+			version: ProofBundle::VERSION,
+			network: "testnet".into(),
+			qr_data: "evt|1700000000|deadbeef01234567".into(),
+			address: "ckt1qattendee".into(),
+			issuer: "ckt1qattendee".into(),
+			message: "CKB-PoP|evt|1700000000|ckt1qattendee".into(),
+			signature: "0xabcd".into(),
+			badge_tx_hash: "0x1234".into(),
+			block_hash: None,
+			signed_at: Some(1_700_000_005),
+			created_at: 1_700_000_010,
+		};
+
+		let json = serde_json::to_string(&bundle).unwrap();
+		let parsed: ProofBundle = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, bundle);
+
+		// Bundles from before `signed_at` was recorded still load.
+		let old = json.replace(r#""signed_at":1700000005,"#, "");
+		let parsed: ProofBundle = serde_json::from_str(&old).unwrap();
+		assert_eq!(parsed.signed_at, None);
+	}
+
+	#[test]
+	fn save_refuses_names_that_are_not_hex() {
+		let mut bundle = ProofBundle {
+			version: ProofBundle::VERSION,
+			network: "testnet".into(),
+			qr_data: String::new(),
+			address: "ckt1qattendee".into(),
+			issuer: "ckt1qattendee".into(),
+			message: String::new(),
+			signature: "0xabcd".into(),
+			badge_tx_hash: "0x1234".into(),
+			block_hash: None,
+			signed_at: None,
+			created_at: 1_700_000_010,
+		};
+		for event_id in ["../../etc/passwd", "évènement-ééééééééé", "abc123"] {
+			assert!(bundle.save(event_id).is_err(), "{event_id}");
+		}
+		bundle.badge_tx_hash = "0x/../x".into();
+		assert!(bundle.save(&"ab".repeat(32)).is_err());
+	}
+}
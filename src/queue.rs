@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::crypto;
+use crate::proof::ProofBundle;
+
+/// An attendance signed at the venue whose badge is not minted yet.
+///
+/// `attend` signs the attendance message while the QR code is fresh and
+/// queues it before minting, so a mint that fails on a bad connection can
+/// be broadcast later with `attend --flush`.  The signature already
+/// commits to the QR timestamp; the mint only has to land eventually.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedAttendance {
+	/// Queue entry format version.
+	pub version: u8,
+	pub network: String,
+	pub event_id: String,
+	/// Raw QR payload as scanned.
+	pub qr_data: String,
+	/// Attendee address that signed the message.
+	pub address: String,
+	/// Address to record as the badge issuer.
+	pub issuer: String,
+	/// The attendance message presented to the wallet.
+	pub message: String,
+	/// Hex-encoded wallet signature over `message`.
+	pub signature: String,
+	/// Unix timestamp at which the message was signed.
+	pub signed_at: i64,
+}
+
+impl QueuedAttendance {
+	pub const VERSION: u8 = 1;
+
+	/// Directory where queued attendances are stored (~/.ckb-pop/queue/).
+	pub fn dir() -> PathBuf {
+		Config::dir().join("queue")
+	}
+
+	/// Write the entry into the queue and return its path.  There is one
+	/// entry per network, event and attendee; signing again replaces it.
+	/// The event ID comes from a scanned QR code and must be hex.
+	pub fn save(&self) -> Result<PathBuf> {
+		if !crypto::is_event_id(&self.event_id) {
+			anyhow::bail!("{:?} is not an event ID", self.event_id);
+		}
+		let dir = Self::dir();
+		std::fs::create_dir_all(&dir)?;
+		let attendee = crypto::sha256_hex(self.address.as_bytes());
+		let name = format!("{}-{}-{}.json", self.network, self.event_id, &attendee[..16]);
+		let path = dir.join(name);
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(path)
+	}
+
+	pub fn load(path: &Path) -> Result<Self> {
+		let content = std::fs::read_to_string(path)
+			.with_context(|| format!("could not read {}", path.display()))?;
+		serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid queued attendance", path.display()))
+	}
+
+	/// Queued entries for `network` with their paths, oldest first.
+	pub fn list(network: &str) -> Result<Vec<(PathBuf, Self)>> {
+		let dir = Self::dir();
+		if !dir.exists() {
+			return Ok(Vec::new());
+		}
+		let mut entries = Vec::new();
+		for entry in std::fs::read_dir(&dir)? {
+			let path = entry?.path();
+			if path.extension().is_none_or(|e| e != "json") {
+				continue;
+			}
+			match Self::load(&path) {
+				Ok(queued) if queued.network == network => entries.push((path, queued)),
+				Ok(_) => {}
+				Err(e) => eprintln!("Warning: skipping {}: {e:#}", path.display()),
+			}
+		}
+		entries.sort_by_key(|(_, queued)| queued.signed_at);
+		Ok(entries)
+	}
+
+	/// Whether badge cell `data` commits to this attendance's signature,
+	/// i.e. the badge was minted from this entry.
+	pub fn minted_as(&self, data: &[u8]) -> bool {
+		let proof_hash = crypto::proof_hash(&self.signature);
+		crypto::build_badge_cell_data(&self.event_id, &self.issuer, Some(&proof_hash)) == data
+	}
+
+	/// The proof bundle for this attendance once its badge is minted in
+	/// `badge_tx_hash`.
+	pub fn into_bundle(self, badge_tx_hash: String, block_hash: Option<String>) -> ProofBundle {
+		ProofBundle {
+			version: ProofBundle::VERSION,
+			network: self.network,
+			qr_data: self.qr_data,
+			address: self.address,
+			issuer: self.issuer,
+			message: self.message,
+			signature: self.signature,
+			badge_tx_hash,
+			block_hash,
+			signed_at: Some(self.signed_at),
+			created_at: chrono::Utc::now().timestamp(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn queued_attendance_becomes_a_bundle() {
+		let queued = QueuedAttendance {
+			version: QueuedAttendance::VERSION,
+			network: "testnet".into(),
+			event_id: "evt".into(),
+			qr_data: "v2|evt|1700000000|deadbeef01234567|n=testnet".into(),
+			address: "ckt1qattendee".into(),
+			issuer: "ckt1qattendee".into(),
+			message: "CKB-PoP|evt|1700000000|ckt1qattendee".into(),
+			signature: "0xabcd".into(),
+			signed_at: 1_700_000_012,
+		};
+		let json = serde_json::to_string(&queued).unwrap();
+		assert_eq!(serde_json::from_str::<QueuedAttendance>(&json).unwrap(), queued);
+
+		let proof_hash = crypto::proof_hash(&queued.signature);
+		let data = crypto::build_badge_cell_data("evt", "ckt1qattendee", Some(&proof_hash));
+		assert!(queued.minted_as(&data));
+		let other = crypto::build_badge_cell_data("evt", "ckt1qattendee", Some(&crypto::proof_hash("")));
+		assert!(!queued.minted_as(&other));
+
+		let bundle = queued.clone().into_bundle("0x1234".into(), None);
+		assert_eq!(bundle.message, queued.message);
+		assert_eq!(bundle.signature, queued.signature);
+		assert_eq!(bundle.badge_tx_hash, "0x1234");
+		assert_eq!(bundle.signed_at, Some(1_700_000_012));
+	}
+
+	#[test]
+	fn save_rejects_paths_as_event_ids() {
+		let queued = QueuedAttendance {
+			version: QueuedAttendance::VERSION,
+			network: "testnet".into(),
+			event_id: "../../config".into(),
+			qr_data: String::new(),
+			address: "ckt1qattendee".into(),
+			issuer: "ckt1qattendee".into(),
+			message: String::new(),
+			signature: "0xabcd".into(),
+			signed_at: 1_700_000_012,
+		};
+		let err = queued.save().unwrap_err();
+		assert!(err.to_string().contains("is not an event ID"), "{err}");
+	}
+}
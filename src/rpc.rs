@@ -1,177 +1,640 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
 use anyhow::{anyhow, Result};
 use ckb_jsonrpc_types as json;
-use ckb_sdk::rpc::CkbRpcClient;
-use serde_json::{json as json_val, Value};
+use ckb_types::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use sha2::{Digest, Sha256};
 
-/// Thin wrapper around the CKB RPC node and its built-in indexer.
+/// Upper bound on a single JSON-RPC round trip.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound on establishing the TCP/TLS connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound on a health probe against a candidate endpoint.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Total attempts per call, across all endpoints.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// First retry delay; doubles on every further attempt.
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Node error code for a transaction that is already in the pool.
+const ERR_DUPLICATED_TX: i64 = -1107;
+
+/// Async client for the CKB RPC node and its built-in indexer.
+///
+/// Every call goes through one typed JSON-RPC layer over `reqwest`, so
+/// nothing blocks the Tokio runtime and a single client can be shared
+/// across concurrent tasks.  Responses are decoded into the
+/// `ckb-jsonrpc-types` models, and indexer queries into the typed
+/// [`SearchKey`] / [`IndexerCell`] models below.
 ///
-/// Most queries go through the ckb-sdk client.  For the indexer's
-/// `get_cells` call we use raw JSON-RPC because ckb-sdk does not
-/// expose the `script_search_mode: "prefix"` parameter that is
-/// essential for searching by partial type-script args.
+/// The client holds an ordered list of endpoints.  Transport failures are
+/// retried with exponential backoff, failing over to the next endpoint
+/// that answers a `get_tip_block_number` probe.  Node errors are never
+/// retried: they are deterministic answers, not outages.  The client
+/// prints nothing itself; callers that want to report retries install a
+/// hook with [`RpcClient::on_retry`].
 pub struct RpcClient {
-	sdk: CkbRpcClient,
-	url: String,
+	endpoints: Vec<String>,
+	active: AtomicUsize,
 	http: reqwest::Client,
+	next_id: AtomicU64,
+	on_retry: Option<RetryHook>,
+}
+
+/// Called with the endpoint and the transport error before a call is
+/// retried.
+pub type RetryHook = fn(&str, &anyhow::Error);
+
+/// Errors surfaced by [`RpcClient`].
+#[derive(Debug, thiserror::Error)]
+pub enum RpcError {
+	/// The request never produced a response (connect, timeout, TLS...).
+	#[error("{method}: transport error: {source}")]
+	Transport {
+		method: String,
+		#[source]
+		source: reqwest::Error,
+	},
+	/// The node answered with a JSON-RPC error object.
+	#[error("{method}: node error {code}: {message}")]
+	Node {
+		method: String,
+		code: i64,
+		message: String,
+		data: Option<Value>,
+	},
+	/// The response could not be decoded into the expected type.
+	#[error("{method}: unexpected response: {reason}")]
+	Decode { method: String, reason: String },
+}
+
+#[derive(Serialize)]
+struct Request<'a, P> {
+	jsonrpc: &'static str,
+	id: u64,
+	method: &'a str,
+	params: P,
+}
+
+#[derive(Deserialize)]
+struct Response<R> {
+	result: Option<R>,
+	error: Option<ErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct ErrorObject {
+	code: i64,
+	message: String,
+	data: Option<Value>,
 }
 
 impl RpcClient {
 	pub fn new(url: &str) -> Self {
+		Self::with_endpoints(vec![url.to_owned()])
+	}
+
+	/// Build a client over several endpoints, preferred one first.
+	pub fn with_endpoints(endpoints: Vec<String>) -> Self {
+		assert!(!endpoints.is_empty(), "at least one RPC endpoint is required");
+		let http = reqwest::Client::builder()
+			.timeout(REQUEST_TIMEOUT)
+			.connect_timeout(CONNECT_TIMEOUT)
+			.build()
+			.expect("failed to build HTTP client");
 		Self {
-			sdk: CkbRpcClient::new(url),
-			url: url.to_owned(),
-			http: reqwest::Client::new(),
+			endpoints,
+			active: AtomicUsize::new(0),
+			http,
+			next_id: AtomicU64::new(1),
+			on_retry: None,
+		}
+	}
+
+	/// Report every retried transport failure to `hook`.
+	pub fn on_retry(mut self, hook: RetryHook) -> Self {
+		self.on_retry = Some(hook);
+		self
+	}
+
+	/// Issue one JSON-RPC call and decode its `result`, retrying transport
+	/// failures against healthy endpoints.  Only use this for idempotent
+	/// methods; every RPC this client exposes is one.
+	///
+	/// A `null` result is decoded as-is, so methods that may legitimately
+	/// return nothing should ask for an `Option<T>`.
+	pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R>
+	where
+		P: Serialize,
+		R: DeserializeOwned,
+	{
+		let mut attempt = 1;
+		loop {
+			let index = self.active.load(Ordering::Relaxed);
+			match self.call_once(&self.endpoints[index], method, &params).await {
+				Err(e) if is_transient(&e) && attempt < MAX_ATTEMPTS => {
+					if let Some(hook) = self.on_retry {
+						hook(&self.endpoints[index], &e);
+					}
+					self.fail_over(index).await;
+					tokio::time::sleep(BASE_RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+					attempt += 1;
+				}
+				result => return result,
+			}
 		}
 	}
 
-	/// Access the underlying ckb-sdk client for operations that it
-	/// handles well (sending transactions, fetching blocks, etc.).
-	#[allow(dead_code)]
-	pub fn sdk(&self) -> &CkbRpcClient {
-		&self.sdk
+	/// Probe every endpoint and report its tip block number or error.
+	pub async fn health(&self) -> Vec<(String, Result<u64>)> {
+		let mut report = Vec::with_capacity(self.endpoints.len());
+		for url in &self.endpoints {
+			report.push((url.clone(), self.probe(url).await));
+		}
+		report
+	}
+
+	/// After `failed` stopped answering, switch to the next endpoint that
+	/// passes a health probe.  If none does, plain round-robin so the
+	/// next attempt at least tries somewhere else.
+	async fn fail_over(&self, failed: usize) {
+		let n = self.endpoints.len();
+		if n == 1 {
+			return;
+		}
+		let mut next = (failed + 1) % n;
+		for offset in 1..n {
+			let candidate = (failed + offset) % n;
+			if self.probe(&self.endpoints[candidate]).await.is_ok() {
+				next = candidate;
+				break;
+			}
+		}
+		// Another task may already have moved on; only the first one wins.
+		let _ = self
+			.active
+			.compare_exchange(failed, next, Ordering::Relaxed, Ordering::Relaxed);
+	}
+
+	async fn probe(&self, url: &str) -> Result<u64> {
+		let tip = tokio::time::timeout(
+			PROBE_TIMEOUT,
+			self.call_once::<_, json::BlockNumber>(url, "get_tip_block_number", &()),
+		)
+		.await
+		.map_err(|_| anyhow!("{url}: health probe timed out"))??;
+		Ok(tip.into())
+	}
+
+	async fn call_once<P, R>(&self, url: &str, method: &str, params: &P) -> Result<R>
+	where
+		P: Serialize,
+		R: DeserializeOwned,
+	{
+		let body = Request {
+			jsonrpc: "2.0",
+			id: self.next_id.fetch_add(1, Ordering::Relaxed),
+			method,
+			params,
+		};
+
+		let transport = |source| RpcError::Transport {
+			method: method.to_owned(),
+			source,
+		};
+		let resp = self
+			.http
+			.post(url)
+			.json(&body)
+			.send()
+			.await
+			.map_err(transport)?;
+		let resp: Response<Value> = resp.json().await.map_err(transport)?;
+
+		if let Some(err) = resp.error {
+			return Err(RpcError::Node {
+				method: method.to_owned(),
+				code: err.code,
+				message: err.message,
+				data: err.data,
+			}
+			.into());
+		}
+
+		serde_json::from_value(resp.result.unwrap_or(Value::Null)).map_err(|e| {
+			RpcError::Decode {
+				method: method.to_owned(),
+				reason: e.to_string(),
+			}
+			.into()
+		})
 	}
 
 	// -- Standard RPC helpers --
 
-	#[allow(dead_code)]
-	pub fn get_tip_block_number(&self) -> Result<u64> {
-		Ok(self.sdk.get_tip_block_number()?.into())
+	pub async fn get_tip_block_number(&self) -> Result<u64> {
+		let tip: json::BlockNumber = self.call("get_tip_block_number", ()).await?;
+		Ok(tip.into())
 	}
 
-	pub fn get_transaction(
+	pub async fn get_transaction(
 		&self,
 		tx_hash: &str,
 	) -> Result<Option<json::TransactionWithStatusResponse>> {
 		let h256 = parse_h256(tx_hash)?;
-		Ok(self.sdk.get_transaction(h256)?)
+		self.call("get_transaction", [h256]).await
 	}
 
-	pub fn send_transaction(&self, tx: json::Transaction) -> Result<ckb_types::H256> {
-		let hash = self
-			.sdk
-			.send_transaction(tx, Some(json::OutputsValidator::Passthrough))?;
-		Ok(hash)
+	/// Fetch a cell by out point, with its data.  `status` is `live` only
+	/// while the cell is unspent.
+	pub async fn get_live_cell(&self, out_point: json::OutPoint) -> Result<json::CellWithStatus> {
+		self.call("get_live_cell", (out_point, true)).await
 	}
 
-	// -- Indexer queries with prefix support --
-
-	/// Run a single paginated `get_cells` call against the indexer.
-	pub async fn get_cells(
-		&self,
-		search_key: Value,
-		order: &str,
-		limit: u64,
-		after_cursor: Option<&str>,
-	) -> Result<Value> {
-		let cursor = after_cursor
-			.map(|s| Value::String(s.to_owned()))
-			.unwrap_or(Value::Null);
-
-		let body = json_val!({
-			"id": 1,
-			"jsonrpc": "2.0",
-			"method": "get_cells",
-			"params": [search_key, order, format!("0x{limit:x}"), cursor]
-		});
-
-		let resp: Value = self.http.post(&self.url).json(&body).send().await?.json().await?;
-
-		resp.get("result").cloned().ok_or_else(|| {
-			let err = resp.get("error").cloned().unwrap_or(Value::Null);
-			anyhow!("get_cells RPC error: {err}")
-		})
+	pub async fn get_header(&self, block_hash: &str) -> Result<Option<json::HeaderView>> {
+		let h256 = parse_h256(block_hash)?;
+		self.call("get_header", [h256]).await
 	}
 
-	/// Collect all pages from a `get_cells` query into a single vec.
-	pub async fn get_all_cells(&self, search_key: Value) -> Result<Vec<Value>> {
-		let mut all = Vec::new();
-		let mut cursor: Option<String> = None;
+	/// Broadcast a transaction.  Safe to retry: the hash is deterministic,
+	/// so if an earlier attempt already reached a node (duplicate in the
+	/// pool, or already committed) this reports success with that hash.
+	pub async fn send_transaction(&self, tx: json::Transaction) -> Result<ckb_types::H256> {
+		let tx_hash: ckb_types::H256 = ckb_types::packed::Transaction::from(tx.clone())
+			.calc_tx_hash()
+			.unpack();
 
-		loop {
-			let page = self
-				.get_cells(search_key.clone(), "asc", 100, cursor.as_deref())
-				.await?;
+		let err = match self
+			.call("send_transaction", (tx, json::OutputsValidator::Passthrough))
+			.await
+		{
+			Ok(hash) => return Ok(hash),
+			Err(e) => e,
+		};
+
+		if let Some(RpcError::Node { code: ERR_DUPLICATED_TX, .. }) = err.downcast_ref() {
+			return Ok(tx_hash);
+		}
+		let status = self
+			.get_transaction(&format!("{tx_hash:#x}"))
+			.await
+			.ok()
+			.flatten()
+			.map(|t| t.tx_status.status);
+		match status {
+			Some(json::Status::Pending | json::Status::Proposed | json::Status::Committed) => {
+				Ok(tx_hash)
+			}
+			_ => Err(err),
+		}
+	}
 
-			let objects = page
-				.get("objects")
-				.and_then(Value::as_array)
-				.cloned()
-				.unwrap_or_default();
+	/// Run the node's full verification on `tx` without adding it to the
+	/// pool.  Returns the cycles and fee the pool would record.
+	pub async fn test_tx_pool_accept(&self, tx: json::Transaction) -> Result<json::EntryCompleted> {
+		self.call("test_tx_pool_accept", (tx, json::OutputsValidator::Passthrough))
+			.await
+	}
 
-			if objects.is_empty() {
-				break;
+	// -- Confirmation tracking --
+
+	/// Fetch the current confirmation state of a transaction.
+	pub async fn tx_progress(&self, tx_hash: &str) -> Result<TxProgress> {
+		let Some(info) = self.get_transaction(tx_hash).await? else {
+			return Ok(TxProgress::Unknown);
+		};
+		let status = info.tx_status;
+		Ok(match status.status {
+			json::Status::Unknown => TxProgress::Unknown,
+			json::Status::Pending => TxProgress::Pending,
+			json::Status::Proposed => TxProgress::Proposed,
+			json::Status::Rejected => TxProgress::Rejected(
+				status.reason.unwrap_or_else(|| "no reason given".into()),
+			),
+			json::Status::Committed => {
+				let block_number: u64 = status
+					.block_number
+					.ok_or_else(|| anyhow!("committed tx has no block number"))?
+					.into();
+				let block_hash = status
+					.block_hash
+					.ok_or_else(|| anyhow!("committed tx has no block hash"))?;
+				let tip = self.get_tip_block_number().await?;
+				TxProgress::Committed(Committed {
+					block_hash,
+					block_number,
+					depth: tip.saturating_sub(block_number),
+				})
 			}
+		})
+	}
 
-			all.extend(objects);
+	/// Poll until the transaction is committed and buried under
+	/// `confirmations` further blocks, it is rejected, or `timeout`
+	/// elapses.  `on_progress` sees every state change.
+	pub async fn wait_for_tx(
+		&self,
+		tx_hash: &str,
+		confirmations: u64,
+		timeout: Option<Duration>,
+		mut on_progress: impl FnMut(&TxProgress),
+	) -> Result<TxOutcome> {
+		let deadline = timeout.map(|t| tokio::time::Instant::now() + t);
+		let mut last: Option<TxProgress> = None;
 
-			cursor = page
-				.get("last_cursor")
-				.and_then(Value::as_str)
-				.map(str::to_owned);
+		loop {
+			let progress = self.tx_progress(tx_hash).await?;
+			if last.as_ref() != Some(&progress) {
+				on_progress(&progress);
+			}
 
-			if cursor.is_none() {
-				break;
+			match &progress {
+				TxProgress::Committed(c) if c.depth >= confirmations => {
+					return Ok(TxOutcome::Confirmed(c.clone()));
+				}
+				TxProgress::Rejected(reason) => return Ok(TxOutcome::Rejected(reason.clone())),
+				_ => {}
 			}
-		}
 
-		Ok(all)
+			if deadline.is_some_and(|d| tokio::time::Instant::now() >= d) {
+				return Ok(TxOutcome::TimedOut(progress));
+			}
+			last = Some(progress);
+			tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+		}
 	}
 
-	// -- PoP-specific search helpers --
+	// -- Indexer queries --
 
-	/// Find all badge cells minted for a given event (prefix match on
-	/// the first 32 bytes of type-script args = SHA256(event_id)).
-	#[allow(dead_code)]
-	pub async fn find_badges_for_event(
+	/// Run a single paginated `get_cells` call against the indexer.
+	pub async fn get_cells(
 		&self,
-		badge_code_hash: &str,
-		event_id: &str,
-	) -> Result<Vec<Value>> {
-		let event_hash = hex::encode(&Sha256::digest(event_id.as_bytes())[..20]);
-		self.get_all_cells(type_prefix_search(badge_code_hash, &event_hash))
-			.await
+		search_key: &SearchKey,
+		order: Order,
+		limit: u32,
+		after_cursor: Option<&json::JsonBytes>,
+	) -> Result<Pagination<IndexerCell>> {
+		self.call(
+			"get_cells",
+			(search_key, order, json::Uint32::from(limit), after_cursor),
+		)
+		.await
 	}
 
+	/// Stream every cell matching `search_key`, fetching one page at a
+	/// time as the caller consumes them.
+	pub fn stream_cells(&self, search_key: SearchKey) -> CellStream<'_> {
+		CellStream {
+			rpc: self,
+			search_key,
+			cursor: None,
+			buffer: VecDeque::new(),
+			done: false,
+		}
+	}
+
+	// -- PoP-specific search helpers --
+
 	/// Find all badge cells across all events (empty prefix on args).
-	pub async fn find_all_badges(&self, badge_code_hash: &str) -> Result<Vec<Value>> {
-		self.get_all_cells(type_prefix_search(badge_code_hash, ""))
-			.await
+	pub fn find_all_badges(&self, badge_code_hash: &str) -> Result<CellStream<'_>> {
+		let key = SearchKey::type_script(badge_code_hash, Vec::new(), SearchMode::Prefix)?;
+		Ok(self.stream_cells(key))
 	}
 
 	/// Find event-anchor cells for a given event ID.
-	pub async fn find_event_anchors(
+	pub fn find_event_anchors(
 		&self,
 		anchor_code_hash: &str,
 		event_id: &str,
-	) -> Result<Vec<Value>> {
-		let event_hash = hex::encode(&Sha256::digest(event_id.as_bytes())[..20]);
-		self.get_all_cells(type_prefix_search(anchor_code_hash, &event_hash))
-			.await
+	) -> Result<CellStream<'_>> {
+		let event_hash = Sha256::digest(event_id.as_bytes())[..20].to_vec();
+		let key = SearchKey::type_script(anchor_code_hash, event_hash, SearchMode::Prefix)?;
+		Ok(self.stream_cells(key))
 	}
 
 	/// Find all event-anchor cells (every event).
-	pub async fn find_all_event_anchors(&self, anchor_code_hash: &str) -> Result<Vec<Value>> {
-		self.get_all_cells(type_prefix_search(anchor_code_hash, ""))
-			.await
+	pub fn find_all_event_anchors(&self, anchor_code_hash: &str) -> Result<CellStream<'_>> {
+		let key = SearchKey::type_script(anchor_code_hash, Vec::new(), SearchMode::Prefix)?;
+		Ok(self.stream_cells(key))
+	}
+
+	/// Look up the single cell whose type script matches exactly.
+	pub async fn find_exact_type_cell(
+		&self,
+		code_hash: &str,
+		args: Vec<u8>,
+	) -> Result<Option<IndexerCell>> {
+		let key = SearchKey::type_script(code_hash, args, SearchMode::Exact)?;
+		let page = self.get_cells(&key, Order::Asc, 1, None).await?;
+		Ok(page.objects.into_iter().next())
+	}
+}
+
+// -- Confirmation models --
+
+/// How often [`RpcClient::wait_for_tx`] polls the node.
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Where a committed transaction sits in the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Committed {
+	pub block_hash: ckb_types::H256,
+	pub block_number: u64,
+	/// Blocks built on top of the commit block (0 = just committed).
+	pub depth: u64,
+}
+
+/// A snapshot of a transaction's lifecycle.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxProgress {
+	/// The node has never seen the transaction, or has dropped it.
+	Unknown,
+	Pending,
+	Proposed,
+	Committed(Committed),
+	/// Rejected from the pool, with the node's reason.
+	Rejected(String),
+}
+
+/// How [`RpcClient::wait_for_tx`] ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxOutcome {
+	Confirmed(Committed),
+	Rejected(String),
+	/// Gave up waiting; carries the last state observed.
+	TimedOut(TxProgress),
+}
+
+// -- Indexer models --
+
+/// Number of cells requested per `get_cells` page when streaming.
+const PAGE_SIZE: u32 = 100;
+
+/// Which script of a cell a [`SearchKey`] matches against.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptType {
+	Lock,
+	Type,
+}
+
+/// How the indexer compares script args or output data.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+	Prefix,
+	Exact,
+}
+
+/// Result ordering for indexer queries.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+	Asc,
+}
+
+/// The indexer's `search_key` parameter.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchKey {
+	pub script: json::Script,
+	pub script_type: ScriptType,
+	pub script_search_mode: SearchMode,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub filter: Option<SearchKeyFilter>,
+	pub with_data: bool,
+}
+
+/// Secondary filters applied after the primary script match.  Ranges are
+/// half-open `[start, end)`, as the indexer defines them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchKeyFilter {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub script: Option<json::Script>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub script_len_range: Option<[json::Uint64; 2]>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_data: Option<json::JsonBytes>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_data_filter_mode: Option<SearchMode>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_data_len_range: Option<[json::Uint64; 2]>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub output_capacity_range: Option<[json::Uint64; 2]>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub block_range: Option<[json::BlockNumber; 2]>,
+}
+
+impl SearchKey {
+	/// Match cells whose type script has `code_hash` (hash type `type`)
+	/// and whose args compare to `args` under `mode`.
+	pub fn type_script(code_hash: &str, args: Vec<u8>, mode: SearchMode) -> Result<Self> {
+		Ok(Self {
+			script: json::Script {
+				code_hash: parse_h256(code_hash)?,
+				hash_type: json::ScriptHashType::Type,
+				args: json::JsonBytes::from_vec(args),
+			},
+			script_type: ScriptType::Type,
+			script_search_mode: mode,
+			filter: None,
+			with_data: true,
+		})
+	}
+
+	/// Match plain capacity cells locked by `lock`: no type script and no
+	/// data, i.e. cells a wallet may freely spend to pay for outputs.
+	pub fn capacity_cells(lock: json::Script) -> Self {
+		Self {
+			script: lock,
+			script_type: ScriptType::Lock,
+			script_search_mode: SearchMode::Exact,
+			filter: Some(SearchKeyFilter {
+				script_len_range: Some([0.into(), 1.into()]),
+				output_data_len_range: Some([0.into(), 1.into()]),
+				..Default::default()
+			}),
+			with_data: false,
+		}
+	}
+}
+
+/// One page of indexer results.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Pagination<T> {
+	pub objects: Vec<T>,
+	pub last_cursor: json::JsonBytes,
+}
+
+/// A live cell as returned by the indexer.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexerCell {
+	pub output: json::CellOutput,
+	pub output_data: Option<json::JsonBytes>,
+	pub out_point: json::OutPoint,
+}
+
+impl IndexerCell {
+	/// Raw output data (empty if the query did not ask for data).
+	pub fn data(&self) -> &[u8] {
+		self.output_data.as_ref().map_or(&[], |d| d.as_bytes())
+	}
+
+	/// Output data decoded as JSON (event-anchor cells).
+	pub fn json_data(&self) -> Option<Value> {
+		serde_json::from_slice(self.data()).ok()
+	}
+
+	/// Type-script args, if the cell has a type script.
+	pub fn type_args(&self) -> Option<&[u8]> {
+		self.output.type_.as_ref().map(|s| s.args.as_bytes())
+	}
+
+	/// The creating transaction's hash, 0x-prefixed.
+	pub fn tx_hash(&self) -> String {
+		format!("{:#x}", self.out_point.tx_hash)
+	}
+}
+
+/// Lazily paginated stream over indexer results; see
+/// [`RpcClient::stream_cells`].
+pub struct CellStream<'a> {
+	rpc: &'a RpcClient,
+	search_key: SearchKey,
+	cursor: Option<json::JsonBytes>,
+	buffer: VecDeque<IndexerCell>,
+	done: bool,
+}
+
+impl CellStream<'_> {
+	/// Yield the next cell, fetching another page when the buffer runs dry.
+	pub async fn next(&mut self) -> Result<Option<IndexerCell>> {
+		if self.buffer.is_empty() && !self.done {
+			let page = self
+				.rpc
+				.get_cells(&self.search_key, Order::Asc, PAGE_SIZE, self.cursor.as_ref())
+				.await?;
+			// A short page is the last one; no need for an empty round trip.
+			self.done = page.objects.len() < PAGE_SIZE as usize || page.last_cursor.is_empty();
+			self.cursor = Some(page.last_cursor);
+			self.buffer.extend(page.objects);
+		}
+		Ok(self.buffer.pop_front())
 	}
 }
 
 // -- Private helpers --
 
-/// Build a search key that matches cells whose type script has the given
-/// code hash and whose args start with `hex_prefix`.
-fn type_prefix_search(code_hash: &str, hex_prefix: &str) -> Value {
-	json_val!({
-		"script": {
-			"code_hash": code_hash,
-			"hash_type": "type",
-			"args": format!("0x{hex_prefix}")
-		},
-		"script_type": "type",
-		"script_search_mode": "prefix",
-		"with_data": true
-	})
+/// Whether a failed call is worth retrying elsewhere.
+fn is_transient(err: &anyhow::Error) -> bool {
+	matches!(err.downcast_ref::<RpcError>(), Some(RpcError::Transport { .. }))
 }
 
 fn parse_h256(hex_str: &str) -> Result<ckb_types::H256> {
@@ -180,3 +643,138 @@ fn parse_h256(hex_str: &str) -> Result<ckb_types::H256> {
 		.parse()
 		.map_err(|e| anyhow!("invalid 256-bit hash: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock;
+
+	#[tokio::test]
+	async fn waits_for_confirmation_depth() {
+		let url = mock::json_rpc(&[
+			(
+				"get_transaction",
+				r#"{"jsonrpc":"2.0","id":1,"result":{"transaction":null,"cycles":null,"time_added_to_pool":null,"fee":null,"min_replace_fee":null,
+				"tx_status":{"status":"committed","block_number":"0x10","tx_index":"0x1","reason":null,
+				"block_hash":"0x00000000000000000000000000000000000000000000000000000000000000aa"}}}"#,
+			),
+			("get_tip_block_number", r#"{"jsonrpc":"2.0","id":1,"result":"0x13"}"#),
+		])
+		.await;
+		let rpc = RpcClient::new(&url);
+		let hash = format!("0x{}", "01".repeat(32));
+
+		let mut seen = Vec::new();
+		let outcome = rpc
+			.wait_for_tx(&hash, 3, None, |p| seen.push(p.clone()))
+			.await
+			.unwrap();
+		match outcome {
+			TxOutcome::Confirmed(c) => {
+				assert_eq!(c.block_number, 16);
+				assert_eq!(c.depth, 3);
+			}
+			other => panic!("expected confirmation, got {other:?}"),
+		}
+		assert_eq!(seen.len(), 1);
+	}
+
+	#[tokio::test]
+	async fn reports_rejection_reason() {
+		let url = mock::json_rpc(&[(
+			"get_transaction",
+			r#"{"jsonrpc":"2.0","id":1,"result":{"transaction":null,"cycles":null,"time_added_to_pool":null,"fee":null,"min_replace_fee":null,
+			"tx_status":{"status":"rejected","block_number":null,"block_hash":null,"tx_index":null,"reason":"Resolve failed Dead"}}}"#,
+		)])
+		.await;
+		let rpc = RpcClient::new(&url);
+		let hash = format!("0x{}", "01".repeat(32));
+
+		let outcome = rpc.wait_for_tx(&hash, 0, None, |_| {}).await.unwrap();
+		assert_eq!(outcome, TxOutcome::Rejected("Resolve failed Dead".into()));
+	}
+
+	#[tokio::test]
+	async fn decodes_typed_result() {
+		let url =
+			mock::respond_with("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":"0x400"}"#).await;
+		let rpc = RpcClient::new(&url);
+		assert_eq!(rpc.get_tip_block_number().await.unwrap(), 1024);
+	}
+
+	#[tokio::test]
+	async fn surfaces_node_errors() {
+		let url = mock::respond_with(
+			"200 OK",
+			r#"{"jsonrpc":"2.0","id":1,"error":{"code":-301,"message":"TransactionFailedToResolve"}}"#,
+		)
+		.await;
+		let rpc = RpcClient::new(&url);
+		let err = rpc.get_tip_block_number().await.unwrap_err();
+		match err.downcast_ref::<RpcError>() {
+			Some(RpcError::Node { code, .. }) => assert_eq!(*code, -301),
+			other => panic!("expected node error, got {other:?}"),
+		}
+	}
+
+	#[tokio::test]
+	async fn fails_over_to_a_healthy_endpoint() {
+		// Grab a free port, then close it so connections are refused.
+		let dead = {
+			let l = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+			format!("http://{}", l.local_addr().unwrap())
+		};
+		let healthy =
+			mock::respond_with("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":"0x10"}"#).await;
+
+		static RETRIED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+		let rpc = RpcClient::with_endpoints(vec![dead.clone(), healthy])
+			.on_retry(|endpoint, _| RETRIED.lock().unwrap().push(endpoint.to_owned()));
+		assert_eq!(rpc.get_tip_block_number().await.unwrap(), 16);
+		// Later calls stay on the healthy endpoint.
+		assert_eq!(rpc.get_tip_block_number().await.unwrap(), 16);
+		assert_eq!(*RETRIED.lock().unwrap(), [dead]);
+	}
+
+	#[tokio::test]
+	async fn streams_cells_until_short_page() {
+		let url = mock::respond_with(
+			"200 OK",
+			r#"{"jsonrpc":"2.0","id":1,"result":{"last_cursor":"0x01","objects":[{
+				"block_number":"0x10","tx_index":"0x0","output_data":"0x7b7d",
+				"out_point":{"index":"0x1","tx_hash":"0x0000000000000000000000000000000000000000000000000000000000000001"},
+				"output":{"capacity":"0x10","lock":{"args":"0x","code_hash":"0x0000000000000000000000000000000000000000000000000000000000000000","hash_type":"type"},
+				"type":{"args":"0xaabb","code_hash":"0x0000000000000000000000000000000000000000000000000000000000000000","hash_type":"type"}}}]}}"#,
+		)
+		.await;
+		let rpc = RpcClient::new(&url);
+		let mut cells = rpc.find_all_badges(&format!("0x{}", "00".repeat(32))).unwrap();
+
+		let cell = cells.next().await.unwrap().unwrap();
+		assert_eq!(cell.type_args(), Some(&[0xaa, 0xbb][..]));
+		assert_eq!(cell.json_data(), Some(serde_json::json!({})));
+		// One object on a page of 100 means the stream is exhausted.
+		assert!(cells.next().await.unwrap().is_none());
+	}
+
+	#[test]
+	fn search_key_serializes_like_the_indexer_expects() {
+		let code_hash = format!("0x{}", "11".repeat(32));
+		let key = SearchKey::type_script(&code_hash, vec![0xab], SearchMode::Exact).unwrap();
+		let value = serde_json::to_value(&key).unwrap();
+		assert_eq!(value["script"]["args"], "0xab");
+		assert_eq!(value["script"]["hash_type"], "type");
+		assert_eq!(value["script_type"], "type");
+		assert_eq!(value["script_search_mode"], "exact");
+		assert!(value.get("filter").is_none());
+	}
+
+	#[tokio::test]
+	async fn null_result_decodes_as_none() {
+		let url =
+			mock::respond_with("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":null}"#).await;
+		let rpc = RpcClient::new(&url);
+		let hash = format!("0x{}", "00".repeat(32));
+		assert!(rpc.get_header(&hash).await.unwrap().is_none());
+	}
+}
@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::event;
+use crate::manifest;
+
+/// An attendance window as written in a manifest or schedule file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WindowSpec {
+	pub start: String,
+	pub end: String,
+}
+
+/// A validated attendance window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+	pub start: DateTime<Utc>,
+	pub end: DateTime<Utc>,
+}
+
+/// Validate windows and sort them by start.  Each must end after it
+/// starts, and windows may not overlap.
+pub fn parse_windows(specs: &[WindowSpec]) -> Result<Vec<Window>> {
+	let mut windows = specs
+		.iter()
+		.enumerate()
+		.map(|(i, w)| {
+			let start = event::parse_time(&format!("windows[{i}].start"), &w.start)?;
+			let end = event::parse_time(&format!("windows[{i}].end"), &w.end)?;
+			if end <= start {
+				anyhow::bail!("windows[{i}] must end after it starts");
+			}
+			Ok(Window { start, end })
+		})
+		.collect::<Result<Vec<_>>>()?;
+	windows.sort_by_key(|w| w.start);
+	if let Some(pair) = windows.windows(2).find(|p| p[1].start < p[0].end) {
+		anyhow::bail!(
+			"windows {} - {} and {} - {} overlap",
+			event::rfc3339(pair[0].start),
+			event::rfc3339(pair[0].end),
+			event::rfc3339(pair[1].start),
+			event::rfc3339(pair[1].end)
+		);
+	}
+	Ok(windows)
+}
+
+/// A file listing attendance windows for `event window --schedule`, in the
+/// same `[[windows]]` form as an event manifest.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleFile {
+	pub windows: Vec<WindowSpec>,
+}
+
+impl ScheduleFile {
+	/// Read a schedule and return its validated windows.
+	pub fn load(path: &Path) -> Result<Vec<Window>> {
+		let file: Self = manifest::read_document(path, "schedule")?;
+		if file.windows.is_empty() {
+			anyhow::bail!("{} lists no windows", path.display());
+		}
+		parse_windows(&file.windows)
+			.with_context(|| format!("{} is not a valid schedule", path.display()))
+	}
+}
+
+/// Where a schedule stands at a given moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+	/// Window `index` is open.
+	Open { index: usize, window: Window },
+	/// No window is open; window `index` opens next.
+	Waiting { index: usize, window: Window },
+	/// Every window has closed.
+	Finished,
+}
+
+/// Where `windows` (sorted, as from [`parse_windows`]) stand at `now`.
+pub fn phase(windows: &[Window], now: DateTime<Utc>) -> Phase {
+	for (index, &window) in windows.iter().enumerate() {
+		if now < window.start {
+			return Phase::Waiting { index, window };
+		}
+		if now < window.end {
+			return Phase::Open { index, window };
+		}
+	}
+	Phase::Finished
+}
+
+/// The organizer's signature over a whole schedule.  One signature covers
+/// every window; each window's QR secret is derived from it and the
+/// window's start.  Saved so that restarting `event window --schedule`
+/// during a multi-day event does not need the wallet again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedSchedule {
+	pub network: String,
+	pub event_id: String,
+	/// The signed message, from [`crate::crypto::schedule_message`].
+	pub message: String,
+	pub signature: String,
+}
+
+impl SignedSchedule {
+	/// Directory where signed schedules are stored (~/.ckb-pop/schedules/).
+	pub fn dir() -> PathBuf {
+		Config::dir().join("schedules")
+	}
+
+	fn path(network: &str, event_id: &str) -> PathBuf {
+		Self::dir().join(format!("{network}-{event_id}.json"))
+	}
+
+	/// The saved signature over `message`, if the schedule is unchanged
+	/// since it was signed.
+	pub fn load(network: &str, event_id: &str, message: &str) -> Result<Option<Self>> {
+		let path = Self::path(network, event_id);
+		if !path.exists() {
+			return Ok(None);
+		}
+		let content = std::fs::read_to_string(&path)?;
+		let saved: Self = serde_json::from_str(&content)
+			.with_context(|| format!("{} is not a valid signed schedule", path.display()))?;
+		Ok((saved.message == message).then_some(saved))
+	}
+
+	pub fn save(&self) -> Result<PathBuf> {
+		std::fs::create_dir_all(Self::dir())?;
+		let path = Self::path(&self.network, &self.event_id);
+		std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+		Ok(path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn spec(start: &str, end: &str) -> WindowSpec {
+		WindowSpec {
+			start: start.into(),
+			end: end.into(),
+		}
+	}
+
+	fn at(time: &str) -> DateTime<Utc> {
+		event::parse_time("time", time).unwrap()
+	}
+
+	#[test]
+	fn schedule_file_matches_manifest_windows() {
+		let toml = r#"
+[[windows]]
+start = "2026-09-02 09:00 +02:00"
+end = "2026-09-02 10:00 +02:00"
+
+[[windows]]
+start = "2026-09-01 09:00 +02:00"
+end = "2026-09-01 10:00 +02:00"
+"#;
+		let file: ScheduleFile = manifest::Format::Toml.parse(toml).unwrap();
+		let windows = parse_windows(&file.windows).unwrap();
+		assert_eq!(windows[0].start, at("2026-09-01T07:00:00Z"));
+		assert_eq!(windows[1].end, at("2026-09-02T08:00:00Z"));
+		assert!(manifest::Format::Toml.parse::<ScheduleFile>("name = \"x\"").is_err());
+	}
+
+	#[test]
+	fn phases_follow_the_windows() {
+		let windows = parse_windows(&[
+			spec("2026-09-01T07:00:00Z", "2026-09-01T08:00:00Z"),
+			spec("2026-09-02T07:00:00Z", "2026-09-02T08:00:00Z"),
+		])
+		.unwrap();
+		let phase_at = |t: &str| phase(&windows, at(t));
+
+		assert!(matches!(phase_at("2026-09-01T06:00:00Z"), Phase::Waiting { index: 0, .. }));
+		assert!(matches!(phase_at("2026-09-01T07:00:00Z"), Phase::Open { index: 0, .. }));
+		assert!(matches!(phase_at("2026-09-01T08:00:00Z"), Phase::Waiting { index: 1, .. }));
+		assert!(matches!(phase_at("2026-09-02T07:59:59Z"), Phase::Open { index: 1, .. }));
+		assert_eq!(phase_at("2026-09-02T08:00:00Z"), Phase::Finished);
+	}
+
+	#[test]
+	fn overlapping_windows_are_rejected() {
+		let err = parse_windows(&[
+			spec("2026-09-01T07:00:00Z", "2026-09-01T08:00:00Z"),
+			spec("2026-09-01T07:30:00Z", "2026-09-01T09:00:00Z"),
+		])
+		.unwrap_err();
+		assert!(err.to_string().contains("overlap"), "{err}");
+		assert!(parse_windows(&[spec("2026-09-01T07:00:00Z", "2026-09-01T06:00:00Z")]).is_err());
+	}
+}
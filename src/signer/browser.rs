@@ -14,11 +14,24 @@ pub struct BrowserSigner {
 }
 
 impl BrowserSigner {
-	pub fn new(address: String, network: String) -> Self {
-		Self { address, network }
+	pub fn new(address: String, network: String) -> Result<Self> {
+		check_network(&network)?;
+		Ok(Self { address, network })
 	}
 }
 
+/// The signing page only has CCC clients for the public chains, which
+/// would resolve a devnet's cells and scripts against testnet.
+fn check_network(network: &str) -> Result<()> {
+	if network == "devnet" {
+		anyhow::bail!(
+			"the browser signer cannot sign for devnet: its wallet client only knows the public \
+			 testnet and mainnet"
+		);
+	}
+	Ok(())
+}
+
 #[async_trait::async_trait]
 impl super::Signer for BrowserSigner {
 	fn address(&self) -> &str {
@@ -59,6 +72,7 @@ impl super::Signer for BrowserSigner {
 /// Open a browser to connect a wallet and return the CKB address.
 /// Used by `signer connect` before any signer instance exists.
 pub async fn connect_wallet(network: &str) -> Result<String> {
+	check_network(network)?;
 	let request = serde_json::json!({
 		"action": "connect",
 		"network": network,
@@ -28,7 +28,7 @@ pub fn from_method(method: &SignerArg, address: String, network: &str) -> Result
 		SignerArg::Browser => Ok(Box::new(browser::BrowserSigner::new(
 			address,
 			network.to_owned(),
-		))),
+		)?)),
 		other => anyhow::bail!("{other:?} signer is not yet implemented"),
 	}
 }
@@ -7,7 +7,7 @@ use ckb_types::{
 	H256,
 };
 
-use crate::contracts::ContractInfo;
+use crate::contracts::{ContractInfo, DepType};
 use crate::crypto;
 
 /// Build an unsigned transaction that creates an event-anchor cell.
@@ -87,7 +87,7 @@ fn set_min_capacity(output: CellOutput, data_len: usize) -> CellOutput {
 }
 
 fn type_script_from(contract: &ContractInfo, args: Vec<u8>) -> Result<Script> {
-	let code_hash = parse_h256(contract.code_hash)?;
+	let code_hash = parse_h256(&contract.code_hash)?;
 	Ok(Script::new_builder()
 		.code_hash(code_hash.pack())
 		.hash_type(ckb_types::core::ScriptHashType::Type)
@@ -96,9 +96,13 @@ fn type_script_from(contract: &ContractInfo, args: Vec<u8>) -> Result<Script> {
 }
 
 fn cell_dep_for(contract: &ContractInfo) -> Result<CellDep> {
-	let tx_hash = parse_h256(contract.deploy_tx_hash)?;
+	let tx_hash = parse_h256(&contract.deploy_tx_hash)?;
 	let out_point = OutPoint::new(tx_hash.pack(), contract.deploy_out_index);
-	Ok(CellDep::new_builder().out_point(out_point).build())
+	let dep_type = match contract.dep_type {
+		DepType::Code => ckb_types::core::DepType::Code,
+		DepType::DepGroup => ckb_types::core::DepType::DepGroup,
+	};
+	Ok(CellDep::new_builder().out_point(out_point).dep_type(dep_type).build())
 }
 
 fn parse_h256(s: &str) -> Result<H256> {
@@ -111,7 +115,7 @@ fn parse_h256(s: &str) -> Result<H256> {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::contracts::CONTRACTS;
+	use crate::contracts::Contracts;
 
 	/// Dummy lock script for testing.
 	fn dummy_lock() -> Script {
@@ -124,7 +128,8 @@ mod tests {
 
 	#[test]
 	fn event_anchor_tx_has_one_output() {
-		let c = CONTRACTS.for_network("testnet");
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
 		let tx = build_event_anchor(
 			&c.event_anchor,
 			"test_event",
@@ -142,7 +147,8 @@ mod tests {
 
 	#[test]
 	fn badge_mint_tx_has_one_output() {
-		let c = CONTRACTS.for_network("testnet");
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
 		let tx = build_badge_mint(
 			&c.dob_badge,
 			"test_event",
@@ -164,7 +170,8 @@ mod tests {
 
 	#[test]
 	fn type_script_args_match_crypto_module() {
-		let c = CONTRACTS.for_network("testnet");
+		let registry = Contracts::builtin();
+		let c = registry.for_network("testnet").unwrap();
 		let tx = build_event_anchor(
 			&c.event_anchor,
 			"myevent",